@@ -0,0 +1,60 @@
+use aws_sdk_s3::primitives::{ByteStream, SdkBody};
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+// Frames are handed to the HTTP client in small chunks, so the client only asks for the
+// last one once everything before it has been written out
+const FRAME_SIZE: usize = 64 * 1024;
+
+// Upload body that remembers when the HTTP client took its last byte
+struct TimedBody {
+    data: Bytes,
+    sent_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Body for TimedBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        if this.data.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        let frame = this.data.split_to(FRAME_SIZE.min(this.data.len()));
+        if this.data.is_empty() {
+            *this.sent_at.lock().unwrap() = Some(Instant::now());
+        }
+        Poll::Ready(Some(Ok(Frame::data(frame))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.data.len() as u64)
+    }
+}
+
+// Builds a retryable PUT body and a handle reporting when it was fully sent
+pub fn timed_byte_stream(data: Bytes) -> (ByteStream, Arc<Mutex<Option<Instant>>>) {
+    let sent_at = Arc::new(Mutex::new(None));
+    let body_sent_at = Arc::clone(&sent_at);
+    let body = SdkBody::retryable(move || {
+        SdkBody::from_body_1_x(TimedBody {
+            data: data.clone(),
+            sent_at: Arc::clone(&body_sent_at),
+        })
+    });
+    (ByteStream::new(body), sent_at)
+}
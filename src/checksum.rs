@@ -0,0 +1,81 @@
+use aws_sdk_s3::operation::get_object::GetObjectOutput;
+use aws_sdk_s3::operation::upload_part::UploadPartOutput;
+use aws_sdk_s3::types::{ChecksumAlgorithm, CompletedPart};
+use rand::Rng;
+
+use crate::BoxError;
+
+// Flexible checksum algorithms traffic is split evenly between, e.g. none,crc32c,sha256.
+// `None` sends no checksum on upload and skips validation on download.
+pub struct ChecksumSplit {
+    algorithms: Vec<Option<ChecksumAlgorithm>>,
+}
+
+impl ChecksumSplit {
+    pub fn parse(spec: &str) -> Result<Self, BoxError> {
+        let mut algorithms = Vec::new();
+        for name in spec.split(',').filter(|name| !name.trim().is_empty()) {
+            let algorithm = match name.trim().to_lowercase().as_str() {
+                "none" => None,
+                "crc32" => Some(ChecksumAlgorithm::Crc32),
+                "crc32c" => Some(ChecksumAlgorithm::Crc32C),
+                "crc64nvme" => Some(ChecksumAlgorithm::Crc64Nvme),
+                "sha1" => Some(ChecksumAlgorithm::Sha1),
+                "sha256" => Some(ChecksumAlgorithm::Sha256),
+                other => {
+                    return Err(format!(
+                        "Unknown checksum algorithm '{}', expected none/crc32/crc32c/crc64nvme/sha1/sha256",
+                        other
+                    ).into());
+                }
+            };
+            if algorithms.contains(&algorithm) {
+                return Err(format!("Checksum algorithm {} listed twice", name.trim()).into());
+            }
+            algorithms.push(algorithm);
+        }
+
+        if algorithms.is_empty() {
+            return Err("No checksum algorithm given".into());
+        }
+        Ok(Self { algorithms })
+    }
+
+    pub fn pick<R: Rng>(&self, rng: &mut R) -> Option<ChecksumAlgorithm> {
+        self.algorithms[rng.random_range(0..self.algorithms.len())].clone()
+    }
+}
+
+pub fn name(algorithm: &ChecksumAlgorithm) -> String {
+    algorithm.as_str().to_lowercase()
+}
+
+// Algorithm of the checksum S3 sent back, the one the SDK validated the body with
+pub fn validated_with(output: &GetObjectOutput) -> Option<ChecksumAlgorithm> {
+    if output.checksum_crc32().is_some() {
+        Some(ChecksumAlgorithm::Crc32)
+    } else if output.checksum_crc32_c().is_some() {
+        Some(ChecksumAlgorithm::Crc32C)
+    } else if output.checksum_crc64_nvme().is_some() {
+        Some(ChecksumAlgorithm::Crc64Nvme)
+    } else if output.checksum_sha1().is_some() {
+        Some(ChecksumAlgorithm::Sha1)
+    } else if output.checksum_sha256().is_some() {
+        Some(ChecksumAlgorithm::Sha256)
+    } else {
+        None
+    }
+}
+
+// CompleteMultipartUpload must repeat the checksum of every part
+pub fn completed_part(part_number: i32, output: &UploadPartOutput) -> CompletedPart {
+    CompletedPart::builder()
+        .part_number(part_number)
+        .set_e_tag(output.e_tag().map(String::from))
+        .set_checksum_crc32(output.checksum_crc32().map(String::from))
+        .set_checksum_crc32_c(output.checksum_crc32_c().map(String::from))
+        .set_checksum_crc64_nvme(output.checksum_crc64_nvme().map(String::from))
+        .set_checksum_sha1(output.checksum_sha1().map(String::from))
+        .set_checksum_sha256(output.checksum_sha256().map(String::from))
+        .build()
+}
@@ -0,0 +1,203 @@
+use aws_sdk_s3::config::endpoint::{DefaultResolver, EndpointFuture, Params, ResolveEndpoint};
+use aws_sdk_s3::config::{RuntimeComponents, SharedHttpClient};
+use aws_smithy_http_client::tls::rustls_provider::CryptoMode;
+use aws_smithy_http_client::tls::{self, TlsContext, TrustStore};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_types::Document;
+use aws_smithy_types::body::SdkBody;
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector as TcpConnector;
+use hyper_util::rt::TokioExecutor;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, ServerName, UnixTime};
+use std::sync::{Arc, OnceLock};
+
+use crate::BoxError;
+
+// HTTP client trusting an extra CA bundle or no certificate at all. None keeps the SDK's
+// default client, which only trusts the system roots. Both stay on the SDK's own stack,
+// hyper 1 with rustls and aws-lc, so results remain comparable with the default client.
+pub fn http_client(ca_cert: Option<&str>, insecure: bool) -> Result<Option<SharedHttpClient>, BoxError> {
+    if insecure {
+        return Ok(Some(SharedHttpClient::new(InsecureClient::default())));
+    }
+    let Some(path) = ca_cert else {
+        return Ok(None);
+    };
+
+    // The SDK only parses the bundle when it connects and panics on a bad one
+    let pem = std::fs::read(path).map_err(|e| format!("Cannot read CA certificate {}: {}", path, e))?;
+    let certs = CertificateDer::pem_slice_iter(&pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid CA certificate {}: {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", path).into());
+    }
+    let (_, invalid) = RootCertStore::empty().add_parsable_certificates(certs);
+    if invalid > 0 {
+        return Err(format!("{} invalid certificates in {}", invalid, path).into());
+    }
+
+    let context = TlsContext::builder()
+        .with_trust_store(TrustStore::default().with_pem_certificate(pem))
+        .build()?;
+    Ok(Some(
+        aws_smithy_http_client::Builder::new()
+            .tls_provider(tls::Provider::Rustls(CryptoMode::AwsLc))
+            .tls_context(context)
+            .build_https(),
+    ))
+}
+
+// Client for --insecure. The SDK's client can't take a custom certificate verifier, so this
+// one is assembled from the same hyper, hyper-rustls and aws-lc pieces.
+#[derive(Debug, Default)]
+struct InsecureClient {
+    // Built for the settings of the first request, a run's clients all share them
+    connector: OnceLock<SharedHttpConnector>,
+}
+
+impl HttpClient for InsecureClient {
+    fn http_connector(&self, settings: &HttpConnectorSettings, _components: &RuntimeComponents) -> SharedHttpConnector {
+        self.connector
+            .get_or_init(|| SharedHttpConnector::new(InsecureConnector::new(settings)))
+            .clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct InsecureConnector {
+    client: Client<HttpsConnector<TcpConnector>, SdkBody>,
+}
+
+impl InsecureConnector {
+    fn new(settings: &HttpConnectorSettings) -> Self {
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let config = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .expect("the aws-lc provider supports the default protocol versions")
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth();
+
+        let mut tcp = TcpConnector::new();
+        tcp.enforce_http(false);
+        tcp.set_nodelay(true);
+        tcp.set_connect_timeout(settings.connect_timeout());
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(config)
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .wrap_connector(tcp);
+        Self { client: Client::builder(TokioExecutor::new()).build(connector) }
+    }
+}
+
+impl HttpConnector for InsecureConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let client = self.client.clone();
+        HttpConnectorFuture::new(async move {
+            let request = request.try_into_http1x().map_err(|e| ConnectorError::user(e.into()))?;
+            let response = client.request(request).await.map_err(|e| {
+                if e.is_connect() {
+                    ConnectorError::io(e.into())
+                } else {
+                    ConnectorError::other(e.into(), None)
+                }
+            })?;
+            HttpResponse::try_from(response.map(SdkBody::from_body_1_x))
+                .map_err(|e| ConnectorError::other(e.into(), None))
+        })
+    }
+}
+
+// Accepts whatever certificate the server presents, for lab clusters only. Handshake
+// signatures are still checked, so the connection is encrypted as usual.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl AcceptAnyCertificate {
+    fn algorithms(&self) -> &WebPkiSupportedAlgorithms {
+        &self.0.signature_verification_algorithms
+    }
+}
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, self.algorithms())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, self.algorithms())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms().supported_schemes()
+    }
+}
+
+// Signs requests for another service name than s3, as some gateways expect. The name
+// comes from the auth schemes of the resolved endpoint, so it is rewritten there.
+#[derive(Debug)]
+pub struct SigningNameResolver {
+    inner: DefaultResolver,
+    signing_name: String,
+}
+
+impl SigningNameResolver {
+    pub fn new(signing_name: &str) -> Self {
+        Self { inner: DefaultResolver::new(), signing_name: signing_name.to_string() }
+    }
+}
+
+impl ResolveEndpoint for SigningNameResolver {
+    fn resolve_endpoint<'a>(&'a self, params: &'a Params) -> EndpointFuture<'a> {
+        EndpointFuture::new(async move {
+            let endpoint = self.inner.resolve_endpoint(params).await?;
+            let auth_schemes = match endpoint.properties().get("authSchemes") {
+                Some(Document::Array(schemes)) => schemes.iter()
+                    .map(|scheme| match scheme {
+                        Document::Object(scheme) => {
+                            let mut scheme = scheme.clone();
+                            scheme.insert("signingName".to_string(), Document::String(self.signing_name.clone()));
+                            Document::Object(scheme)
+                        }
+                        other => other.clone(),
+                    })
+                    .collect(),
+                _ => return Ok(endpoint),
+            };
+            Ok(endpoint.into_builder().property("authSchemes", Document::Array(auth_schemes)).build())
+        })
+    }
+}
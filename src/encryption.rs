@@ -0,0 +1,101 @@
+use aws_sdk_s3::types::ServerSideEncryption;
+use md5::{Digest, Md5};
+use rand::Rng;
+
+use crate::BoxError;
+
+// Server-side encryption requested for the objects of a run
+#[derive(Clone)]
+pub enum Encryption {
+    None,
+    // SSE-S3, keys managed by the object store
+    S3,
+    // SSE-KMS, with the bucket's default key unless one is given
+    Kms { key_id: Option<String> },
+    // SSE-C, S3 keeps only the key's MD5 so every write and read must send the key
+    Customer { key: String, key_md5: String },
+}
+
+impl Encryption {
+    // none, sse-s3, sse-kms or sse-c. An SSE-C key file holds the base64 encoded 256-bit key,
+    // without one a key is generated and objects can only be read back by the same process.
+    pub fn parse(mode: &str, kms_key_id: Option<&str>, customer_key_file: Option<&str>) -> Result<Self, BoxError> {
+        let mode = mode.trim().to_lowercase();
+        if kms_key_id.is_some() && mode != "sse-kms" {
+            return Err("A KMS key id only applies to sse-kms".into());
+        }
+        if customer_key_file.is_some() && mode != "sse-c" {
+            return Err("An SSE-C key file only applies to sse-c".into());
+        }
+
+        match mode.as_str() {
+            "none" => Ok(Encryption::None),
+            "sse-s3" => Ok(Encryption::S3),
+            "sse-kms" => Ok(Encryption::Kms { key_id: kms_key_id.map(String::from) }),
+            "sse-c" => {
+                let key = match customer_key_file {
+                    Some(path) => {
+                        let encoded = std::fs::read_to_string(path)
+                            .map_err(|e| format!("Cannot read SSE-C key file {}: {}", path, e))?;
+                        aws_smithy_types::base64::decode(encoded.trim())
+                            .map_err(|e| format!("Invalid SSE-C key in {}: {}", path, e))?
+                    }
+                    None => rand::rng().random::<[u8; 32]>().to_vec(),
+                };
+                if key.len() != 32 {
+                    return Err(format!("SSE-C keys are 256 bits, got {}", key.len() * 8).into());
+                }
+                Ok(Encryption::Customer {
+                    key: aws_smithy_types::base64::encode(&key),
+                    key_md5: aws_smithy_types::base64::encode(Md5::digest(&key)),
+                })
+            }
+            other => Err(format!("Unknown encryption mode '{}', expected none/sse-s3/sse-kms/sse-c", other).into()),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encryption::None => "none",
+            Encryption::S3 => "sse-s3",
+            Encryption::Kms { .. } => "sse-kms",
+            Encryption::Customer { .. } => "sse-c",
+        }
+    }
+
+    // Header values for PUT and CreateMultipartUpload
+    pub fn server_side_encryption(&self) -> Option<ServerSideEncryption> {
+        match self {
+            Encryption::S3 => Some(ServerSideEncryption::Aes256),
+            Encryption::Kms { .. } => Some(ServerSideEncryption::AwsKms),
+            Encryption::None | Encryption::Customer { .. } => None,
+        }
+    }
+
+    pub fn kms_key_id(&self) -> Option<String> {
+        match self {
+            Encryption::Kms { key_id } => key_id.clone(),
+            _ => None,
+        }
+    }
+
+    // Header values sent with every request touching the object content under SSE-C:
+    // PUT, multipart upload, part and completion, GET and HEAD
+    pub fn customer_algorithm(&self) -> Option<String> {
+        matches!(self, Encryption::Customer { .. }).then(|| "AES256".to_string())
+    }
+
+    pub fn customer_key(&self) -> Option<String> {
+        match self {
+            Encryption::Customer { key, .. } => Some(key.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn customer_key_md5(&self) -> Option<String> {
+        match self {
+            Encryption::Customer { key_md5, .. } => Some(key_md5.clone()),
+            _ => None,
+        }
+    }
+}
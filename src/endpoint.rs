@@ -0,0 +1,115 @@
+use aws_sdk_s3::Client as S3Client;
+use aws_sdk_s3::config::interceptors::{BeforeSerializationInterceptorContextRef, BeforeTransmitInterceptorContextRef};
+use aws_sdk_s3::config::{ConfigBag, Intercept, RuntimeComponents};
+use rand::Rng;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use crate::BoxError;
+
+// One S3 gateway of the cluster under test
+pub struct Endpoint {
+    pub url: String,
+    pub client: S3Client,
+}
+
+enum Selection {
+    RoundRobin,
+    Random,
+}
+
+// Spreads operations across the endpoints of a run
+pub struct EndpointPool {
+    endpoints: Vec<Endpoint>,
+    selection: Selection,
+    next: AtomicUsize,
+}
+
+impl EndpointPool {
+    // round-robin or random
+    pub fn new(endpoints: Vec<Endpoint>, selection: &str) -> Result<Self, BoxError> {
+        if endpoints.is_empty() {
+            return Err("No endpoint URL given".into());
+        }
+        let selection = match selection.trim().to_lowercase().as_str() {
+            "round-robin" => Selection::RoundRobin,
+            "random" => Selection::Random,
+            other => return Err(format!("Unknown endpoint selection '{}', expected round-robin/random", other).into()),
+        };
+        Ok(Self { endpoints, selection, next: AtomicUsize::new(0) })
+    }
+
+    // Comma-separated URLs, e.g. http://node1:9000,http://node2:9000
+    pub fn parse_urls(spec: &str) -> Vec<String> {
+        spec.split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    // Route of the next operation
+    pub fn route(&self) -> Route<'_> {
+        Route { endpoint: self.pick(), attempts: Attempts::default() }
+    }
+
+    // Endpoint serving the next operation
+    fn pick(&self) -> &Endpoint {
+        let index = match self.selection {
+            Selection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed),
+            Selection::Random => rand::rng().random_range(0..self.endpoints.len()),
+        };
+        &self.endpoints[index % self.endpoints.len()]
+    }
+
+    // Endpoint for the setup calls around a run, such as bucket checks and listing
+    pub fn first(&self) -> &Endpoint {
+        &self.endpoints[0]
+    }
+}
+
+// Where one operation is sent, with the count of the requests it sent there
+pub struct Route<'a> {
+    pub endpoint: &'a Endpoint,
+    pub attempts: Attempts,
+}
+
+// Counts the requests sent through it and the attempts the SDK made for them,
+// attached to each request of an operation so its retries can be reported
+#[derive(Debug, Clone, Default)]
+pub struct Attempts {
+    requests: Arc<AtomicU32>,
+    attempts: Arc<AtomicU32>,
+}
+
+impl Attempts {
+    // Attempts beyond the first of every request
+    pub fn retries(&self) -> u32 {
+        self.attempts.load(Ordering::Relaxed).saturating_sub(self.requests.load(Ordering::Relaxed))
+    }
+}
+
+impl Intercept for Attempts {
+    fn name(&self) -> &'static str {
+        "Attempts"
+    }
+
+    fn read_before_execution(
+        &self,
+        _context: &BeforeSerializationInterceptorContextRef<'_>,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn read_before_attempt(
+        &self,
+        _context: &BeforeTransmitInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
@@ -1,40 +1,175 @@
+mod body;
+mod checksum;
+mod client;
+mod encryption;
+mod endpoint;
+mod metrics;
+mod payload;
+mod range;
+mod scenario;
+mod schedule;
+mod sink;
+mod size;
+mod stats;
+mod workload;
+
 use aws_sdk_s3::{Client as S3Client, Error as S3Error};
 use aws_sdk_s3::types::ByteStream;
+use aws_sdk_s3::config::{ProvideCredentials, Region, RequestChecksumCalculation, ResponseChecksumValidation, SharedCredentialsProvider};
+use aws_sdk_s3::types::{ChecksumAlgorithm, ChecksumMode, CompletedMultipartUpload, CompletedPart};
 use aws_types::credentials::Credentials;
 use aws_config::meta::region::RegionProviderChain;
-use clap::Parser;
-use elasticsearch::{Elasticsearch, http::transport::Transport};
+use aws_config::sts::AssumeRoleProvider;
+use aws_smithy_types::error::display::DisplayErrorContext;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use bytes::Bytes;
 use uuid::Uuid;
 use chrono::Utc;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use std::time::{Instant, Duration};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::task::JoinSet;
+
+use body::timed_byte_stream;
+use checksum::ChecksumSplit;
+use client::SigningNameResolver;
+use encryption::Encryption;
+use endpoint::{Attempts, Endpoint, EndpointPool, Route};
+use metrics::PrometheusMetrics;
+use payload::Payload;
+use range::RangePattern;
+use scenario::Scenario;
+use size::SizeDistribution;
+use schedule::{Arrival, Schedule};
+use sink::{BulkConfig, ElasticConnection, ElasticsearchSink, IndexConfig, MetricsSink, OtlpSink};
+use stats::WorkerStats;
+use workload::{KeySet, Operation, OperationMix, Workload};
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+// Tools next to the benchmark run, which the command line is without a subcommand
+#[derive(Subcommand, Debug)]
+enum Command {
+    Replay(ReplayArgs),
+}
 
 #[derive(Parser, Debug)]
 #[clap(author="Giorgio Zoppi", version="1.0", about="Interactive benchmark tool for S3 operations")]
+// Scenario phases are passed as flags ahead of the command line, the last occurrence wins
+#[clap(args_override_self = true)]
 struct Args {
-    #[clap(short = 'e', long, help = "Endpoint URL for S3 object storage")]
+    #[clap(short = 'e', long, help = "Endpoint URL for S3 object storage, or a comma-separated list of gateways to spread operations across")]
     endpoint_url: String,
 
-    #[clap(short = 'a', long, help = "Access key for S3 object storage")]
-    access_key: String,
+    #[clap(long, default_value = "round-robin", help = "How operations are spread across several endpoints - round-robin or random")]
+    endpoint_selection: String,
+
+    #[clap(short = 'a', long, requires = "secret_key", help = "Access key for S3 object storage, without keys the AWS credential chain is used")]
+    access_key: Option<String>,
+
+    #[clap(short = 's', long, requires = "access_key", help = "Secret key for S3 object storage")]
+    secret_key: Option<String>,
+
+    #[clap(long, conflicts_with = "access_key", help = "Shared config/credentials profile to take credentials from")]
+    profile: Option<String>,
+
+    #[clap(long, help = "Role to assume with the resolved credentials")]
+    role_arn: Option<String>,
+
+    #[clap(long, default_value = "s3newbench", help = "Session name of the assumed role")]
+    role_session_name: String,
 
-    #[clap(short = 's', long, help = "Secret key for S3 object storage")]
-    secret_key: String,
+    #[clap(long, conflicts_with_all = ["access_key", "profile", "role_arn"], help = "Send unsigned requests, for public buckets")]
+    anonymous: bool,
+
+    #[clap(long, help = "Region to sign requests for, by default taken from the AWS environment or us-east-1")]
+    region: Option<String>,
+
+    #[clap(long, help = "Service name to sign requests for, when the store expects another one than s3")]
+    signing_name: Option<String>,
+
+    #[clap(long, help = "Address buckets as ENDPOINT/BUCKET instead of BUCKET.ENDPOINT, as most S3-compatible stores expect")]
+    force_path_style: bool,
+
+    #[clap(long, help = "PEM file with the CA certificates of the S3 endpoint, trusted besides the system ones")]
+    ca_cert: Option<String>,
+
+    #[clap(long, conflicts_with = "ca_cert", help = "Skip S3 certificate validation")]
+    insecure: bool,
 
     #[clap(short = 'b', long, help = "S3 bucket name")]
     bucket_name: String,
 
-    #[clap(short = 'o', long, help = "S3 object size (e.g. 10MB)")]
+    #[clap(short = 'o', long, help = "S3 object size (e.g. 10MB), or a distribution - uniform:4KB-1MB, lognormal:MEDIAN,SIGMA[,MAX] or 4KB:60,1MB:30,64MB:10")]
     object_size: String,
 
-    #[clap(short = 'u', long, help = "Elasticsearch cluster URL")]
-    elastic_url: String,
+    #[clap(long, default_value = "random", help = "Object content - random, fill, compressible:RATIO, dedupe:RATIO or file:PATH with seed data")]
+    payload: String,
+
+    #[clap(long, help = "Derive object content from the key and --verify-seed, and check every byte read back")]
+    verify: bool,
+
+    #[clap(long, default_value_t = 0, help = "Seed of the verifiable content, readers must use the one the objects were written with")]
+    verify_seed: u64,
+
+    #[clap(long, help = "Flexible checksum sent on PUT and validated on GET - none/crc32/crc32c/crc64nvme/sha1/sha256, a comma-separated list splits traffic evenly")]
+    checksum_algorithm: Option<String>,
+
+    #[clap(long, default_value = "none", help = "Server-side encryption of the written objects - none/sse-s3/sse-kms/sse-c")]
+    sse: String,
+
+    #[clap(long, help = "KMS key id used with --sse sse-kms, the bucket default when omitted")]
+    sse_kms_key_id: Option<String>,
+
+    #[clap(long, help = "File with the base64 SSE-C key, needed to read objects written by another run")]
+    sse_c_key_file: Option<String>,
+
+    #[clap(flatten)]
+    elastic: ElasticArgs,
+
+    #[clap(long, default_value_t = 500, help = "Number of documents per Elasticsearch bulk request")]
+    bulk_size: usize,
+
+    #[clap(long, default_value = "1s", help = "Max time buffered documents wait before a bulk request")]
+    bulk_interval: String,
+
+    #[clap(long, default_value_t = 3, help = "Retries of a failed Elasticsearch bulk request")]
+    bulk_retries: u32,
+
+    #[clap(long, default_value = "s3-perf-spill.ndjson", help = "File receiving the documents Elasticsearch could not index")]
+    spill_file: String,
+
+    #[clap(long, default_value = "s3-perf-index", help = "Elasticsearch index or data stream name, strftime patterns such as s3-perf-%Y.%m.%d roll daily")]
+    index: String,
+
+    #[clap(long, help = "Index into a data stream instead of a regular index")]
+    data_stream: bool,
+
+    #[clap(long, help = "Create or update the index template before the run")]
+    install_template: bool,
+
+    #[clap(long, default_value = "s3-perf", help = "Name of the index template installed with --install-template")]
+    template_name: String,
+
+    #[clap(long, help = "OpenTelemetry collector URL (e.g. http://localhost:4318), results are exported there as OTLP spans and metrics")]
+    otlp_endpoint: Option<String>,
+
+    #[clap(long = "sink", help = "Additional result sink - stdout, jsonl:PATH or csv:PATH (repeatable)")]
+    sinks: Vec<String>,
 
     #[clap(short = 'n', long, help = "Number of objects to put/get")]
-    num_objects: usize,
+    num_objects: Option<usize>,
+
+    #[clap(short = 'd', long, help = "Run for a fixed time (e.g. 90s, 30m, 2h), optionally capped by --num-objects")]
+    duration: Option<String>,
 
-    #[clap(short = 'w', long, help = "Workload running on S3 - read/write")]
+    #[clap(short = 'w', long, help = "Workload running on S3 - read/write, or a weighted mix such as get=70,put=20,delete=5,head=5")]
     workload: String,
 
     #[clap(short = 'l', long, help = "Max acceptable latency per object operation in ms")]
@@ -45,49 +180,445 @@ struct Args {
 
     #[clap(short = 'c', long, help = "Should we cleanup all the objects written? yes/no")]
     cleanup: Option<String>,
+
+    #[clap(short = 'j', long, default_value_t = 1, help = "Number of concurrent workers issuing operations")]
+    concurrency: usize,
+
+    #[clap(short = 'r', long, help = "Target rate in ops/s for open-loop load generation")]
+    rate: Option<f64>,
+
+    #[clap(long, default_value = "fixed", help = "Arrival schedule used with --rate - fixed/poisson")]
+    arrival: String,
+
+    #[clap(long, help = "Use multipart upload for objects of at least this size (e.g. 64MB)")]
+    multipart_threshold: Option<String>,
+
+    #[clap(long, default_value = "8MB", help = "Part size for multipart uploads")]
+    part_size: String,
+
+    #[clap(long, default_value_t = 4, help = "Number of parts uploaded in parallel per object")]
+    part_concurrency: usize,
+
+    #[clap(long, help = "Ranged GET pattern for read workloads - tail:SIZE, random:SIZE or sequential:SIZE")]
+    range_pattern: Option<String>,
+
+    #[clap(long, default_value_t = 1, help = "Number of ranges read per object with the random range pattern")]
+    ranges_per_object: usize,
+
+    #[clap(long, help = "Write the end-of-run latency summary as JSON to this file")]
+    summary_file: Option<String>,
+
+    #[clap(long, help = "Serve live Prometheus metrics on this address (e.g. 0.0.0.0:9100)")]
+    metrics_listen: Option<String>,
+
+    #[clap(long, help = "TOML or YAML file describing the phases to run, flags given here override it")]
+    scenario: Option<String>,
+
+    #[clap(long, help = "Phase name recorded in every result document")]
+    phase: Option<String>,
+}
+
+// Pushes the documents spilled by an earlier run into Elasticsearch
+#[derive(clap::Args, Debug)]
+#[clap(about = "Index documents spilled by an earlier run")]
+struct ReplayArgs {
+    #[clap(flatten)]
+    elastic: ElasticArgs,
+
+    #[clap(short = 'f', long, default_value = "s3-perf-spill.ndjson", help = "Spill file written by an earlier run")]
+    file: String,
+
+    #[clap(long, default_value_t = 500, help = "Number of documents per Elasticsearch bulk request")]
+    bulk_size: usize,
+
+    #[clap(long, default_value_t = 3, help = "Retries of a failed Elasticsearch bulk request")]
+    bulk_retries: u32,
+
+    #[clap(long, default_value = "s3-perf-index", help = "Elasticsearch index or data stream name, strftime patterns roll daily")]
+    index: String,
+
+    #[clap(long, help = "Index into a data stream instead of a regular index")]
+    data_stream: bool,
+}
+
+// Results cluster connection, shared by benchmark runs and `replay`.
+// Secrets are read from files or the environment so they stay out of the process list.
+#[derive(clap::Args, Debug)]
+struct ElasticArgs {
+    #[clap(short = 'u', long, help = "Elasticsearch cluster URL, results are indexed there when given")]
+    elastic_url: Option<String>,
+
+    #[clap(long, conflicts_with = "elastic_url", help = "Elastic Cloud ID, used instead of --elastic-url")]
+    elastic_cloud_id: Option<String>,
+
+    #[clap(long, help = "Elasticsearch user for basic auth, the password comes from ELASTIC_PASSWORD or --elastic-password-file")]
+    elastic_username: Option<String>,
+
+    #[clap(long, help = "File holding the Elasticsearch password")]
+    elastic_password_file: Option<String>,
+
+    #[clap(long, help = "File holding the Elasticsearch API key (id:key or base64 encoded), else ELASTIC_API_KEY is used")]
+    elastic_api_key_file: Option<String>,
+
+    #[clap(long, help = "PEM file with the CA certificate of the Elasticsearch cluster")]
+    elastic_ca_cert: Option<String>,
+
+    #[clap(long, conflicts_with = "elastic_ca_cert", help = "Skip Elasticsearch certificate validation")]
+    elastic_insecure: bool,
+
+    #[clap(long, default_value = "30s", help = "Timeout of every request to Elasticsearch")]
+    elastic_timeout: String,
+}
+
+impl ElasticArgs {
+    // None when no results cluster was given
+    fn connection(&self) -> Result<Option<ElasticConnection>, BoxError> {
+        if self.elastic_url.is_none() && self.elastic_cloud_id.is_none() {
+            return Ok(None);
+        }
+
+        let password = sink::read_secret(self.elastic_password_file.as_deref(), "ELASTIC_PASSWORD")?;
+        let api_key = sink::read_secret(self.elastic_api_key_file.as_deref(), "ELASTIC_API_KEY")?;
+        let timeout = ObjectAnalyzer::parse_duration(&self.elastic_timeout)?;
+        if timeout.is_zero() {
+            return Err("The Elasticsearch timeout must be above zero".into());
+        }
+        Ok(Some(ElasticConnection {
+            url: self.elastic_url.clone(),
+            cloud_id: self.elastic_cloud_id.clone(),
+            credentials: sink::credentials(self.elastic_username.as_deref(), password, api_key)?,
+            ca_cert: self.elastic_ca_cert.clone(),
+            insecure: self.elastic_insecure,
+            timeout,
+        }))
+    }
+}
+
+impl Args {
+    fn encryption(&self) -> Result<Encryption, BoxError> {
+        Encryption::parse(&self.sse, self.sse_kms_key_id.as_deref(), self.sse_c_key_file.as_deref())
+    }
+
+    // Checks the specs that are otherwise only parsed once the run starts
+    fn validate(&self) -> Result<(), BoxError> {
+        Workload::parse(&self.workload)?;
+        SizeDistribution::parse(&self.object_size)?;
+        if let Some(duration) = &self.duration {
+            ObjectAnalyzer::parse_duration(duration)?;
+        }
+        if let Some(spec) = &self.range_pattern {
+            RangePattern::parse(spec, self.ranges_per_object)?;
+        }
+        Arrival::parse(&self.arrival)?;
+        Ok(())
+    }
+}
+
+// Largest object S3 accepts in a single PUT
+const MAX_PUT_SIZE: usize = 5 * 1024 * 1024 * 1024;
+// S3 limits on multipart uploads, only the last part may be smaller than the minimum
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+const MAX_PART_SIZE: usize = 5 * 1024 * 1024 * 1024;
+const MAX_PARTS: usize = 10_000;
+
+struct MultipartConfig {
+    threshold: usize,
+    part_size: usize,
+    part_concurrency: usize,
 }
 
 struct ObjectAnalyzer {
-    s3: S3Client,
-    elastic: Elasticsearch,
+    endpoints: EndpointPool,
+    sinks: Vec<Box<dyn MetricsSink>>,
     args: Args,
-    cleanup_list: Vec<String>,
+    object_size: SizeDistribution,
+    payload: Arc<Payload>,
+    checksums: Option<ChecksumSplit>,
+    encryption: Encryption,
+    credential_source: String,
+    region: String,
+    multipart: Option<MultipartConfig>,
+    range_pattern: Option<RangePattern>,
+    metrics: Option<Arc<PrometheusMetrics>>,
+    cleanup_list: Mutex<Vec<String>>,
+}
+
+// Number of keys sampled for a read or mixed run bounded only by --duration
+const DEFAULT_READ_SAMPLE: usize = 1000;
+
+// User metadata holding the length an object was written with, sent as x-amz-meta-s3newbench-size
+const SIZE_METADATA: &str = "s3newbench-size";
+
+// State shared by all workers of a run
+struct RunContext {
+    issued: AtomicUsize,
+    source: String,
+    schedule: Option<Schedule>,
+    max_ops: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl RunContext {
+    fn deadline_reached(&self, at: Instant) -> bool {
+        self.deadline.is_some_and(|deadline| at >= deadline)
+    }
+
+    // Claims the next operation, returning its index and intended start time
+    // (the scheduled slot in open-loop mode, now otherwise). None once the run is over.
+    async fn next_op(&self) -> Option<(usize, Instant)> {
+        if self.deadline_reached(Instant::now()) {
+            return None;
+        }
+
+        let index = self.issued.fetch_add(1, Ordering::Relaxed);
+        if self.max_ops.is_some_and(|max_ops| index >= max_ops) {
+            return None;
+        }
+
+        match &self.schedule {
+            Some(schedule) => {
+                let scheduled = schedule.next_start();
+                if self.deadline_reached(scheduled) {
+                    return None;
+                }
+                tokio::time::sleep_until(tokio::time::Instant::from_std(scheduled)).await;
+                Some((index, scheduled))
+            }
+            None => Some((index, Instant::now())),
+        }
+    }
+}
+
+struct OpTiming {
+    // Time spent in the S3 call itself
+    service_time: Duration,
+    // Time since the operation was scheduled, corrected for coordinated omission
+    response_time: Duration,
+}
+
+impl OpTiming {
+    fn measure(scheduled: Instant, start: Instant) -> Self {
+        let end = Instant::now();
+        Self {
+            service_time: end - start,
+            response_time: end - scheduled,
+        }
+    }
+}
+
+// Split of a transfer into waiting for the response and moving the body
+struct PhaseTiming {
+    // Request fully sent until the response headers arrived
+    ttfb: Duration,
+    // Time spent streaming the body, the upload for PUT and the download for GET
+    transfer_time: Duration,
+}
+
+// Outcome of a single S3 operation, indexed as one document
+struct OpResult {
+    operation: Operation,
+    object_name: String,
+    size_bytes: usize,
+    timing: OpTiming,
+    phases: Option<PhaseTiming>,
+    // Set on the documents of individual parts of a multipart upload
+    part_number: Option<i32>,
+    // Set on the whole-object document of a multipart upload
+    parts: Option<usize>,
+    // Inclusive byte range of a ranged GET
+    range: Option<(u64, u64)>,
+    // Outcome of the content check of a read, None when not verified
+    verified: Option<bool>,
+    // Flexible checksum sent with an upload or validated on a download
+    checksum: Option<ChecksumAlgorithm>,
+    // URL of the gateway that served the operation
+    endpoint: Option<String>,
+    // Attempts the SDK made beyond the first of each request
+    retries: Option<u32>,
+    // Why the operation failed, failures are recorded like completed operations
+    error: Option<String>,
+}
+
+impl OpResult {
+    fn new(operation: Operation, object_name: String, size_bytes: usize, timing: OpTiming) -> Self {
+        Self {
+            operation,
+            object_name,
+            size_bytes,
+            timing,
+            phases: None,
+            part_number: None,
+            parts: None,
+            range: None,
+            verified: None,
+            checksum: None,
+            endpoint: None,
+            retries: None,
+            error: None,
+        }
+    }
+
+    fn set_route(&mut self, route: &Route<'_>) {
+        self.endpoint = Some(route.endpoint.url.clone());
+        self.retries = Some(route.attempts.retries());
+    }
 }
 
 impl ObjectAnalyzer {
-    async fn new(args: Args) -> Result<Self, Box<dyn Error>> {
-        // Setup AWS config
-        let region_provider = RegionProviderChain::default_provider().or_else("us-east-1");
-        let shared_config = aws_config::from_env()
-            .region(region_provider)
-            .credentials_provider(Credentials::new(
-                &args.access_key,
-                &args.secret_key,
-                None,
-                None,
-                "custom",
-            ))
-            .load()
-            .await;
-
-        let s3_config_builder = aws_sdk_s3::config::Builder::from(&shared_config)
-            .endpoint_url(&args.endpoint_url);
-        let s3 = S3Client::from_conf(s3_config_builder.build());
-
-        // Elasticsearch client setup
-        let transport = Transport::single_node(&args.elastic_url)?;
-        let elastic = Elasticsearch::new(transport);
+    // Encryption is parsed by the caller, so the phases of a scenario share a generated SSE-C key
+    async fn new(args: Args, encryption: Encryption, metrics: Option<Arc<PrometheusMetrics>>) -> Result<Self, BoxError> {
+        // Setup AWS config. Static keys win, otherwise the default chain resolves them from
+        // the environment, profiles, web identity or instance metadata
+        let region_provider = RegionProviderChain::first_try(args.region.clone().map(Region::new))
+            .or_default_provider()
+            .or_else("us-east-1");
+        let mut loader = aws_config::from_env().region(region_provider);
+        let mut credential_source = match (&args.access_key, &args.secret_key) {
+            (Some(access_key), Some(secret_key)) => {
+                loader = loader.credentials_provider(Credentials::new(access_key, secret_key, None, None, "custom"));
+                "static".to_string()
+            }
+            _ if args.anonymous => {
+                loader = loader.no_credentials();
+                "anonymous".to_string()
+            }
+            _ => match &args.profile {
+                Some(profile) => {
+                    loader = loader.profile_name(profile);
+                    format!("profile:{}", profile)
+                }
+                None => "default-chain".to_string(),
+            },
+        };
+        let shared_config = loader.load().await;
+
+        let mut credentials = shared_config.credentials_provider();
+        if let Some(role_arn) = &args.role_arn {
+            let provider = AssumeRoleProvider::builder(role_arn)
+                .session_name(&args.role_session_name)
+                .configure(&shared_config)
+                .build()
+                .await;
+            credentials = Some(SharedCredentialsProvider::new(provider));
+            credential_source = format!("assume-role:{} via {}", role_arn, credential_source);
+        }
+        // Resolve once so missing or expired credentials fail before the run starts
+        if let Some(provider) = &credentials {
+            provider.provide_credentials().await
+                .map_err(|e| format!("Cannot load credentials from {}: {}", credential_source, DisplayErrorContext(&e)))?;
+        }
+        println!("Credentials: {}", credential_source);
+
+        let mut s3_config_builder = aws_sdk_s3::config::Builder::from(&shared_config);
+        s3_config_builder.set_credentials_provider(credentials);
+        s3_config_builder.set_http_client(client::http_client(args.ca_cert.as_deref(), args.insecure)?);
+        if args.force_path_style {
+            s3_config_builder = s3_config_builder.force_path_style(true);
+        }
+        if let Some(signing_name) = &args.signing_name {
+            s3_config_builder = s3_config_builder.endpoint_resolver(SigningNameResolver::new(signing_name));
+        }
+        // Only the checksums under test are computed, not the SDK's default CRC32
+        if args.checksum_algorithm.is_some() {
+            s3_config_builder = s3_config_builder
+                .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+                .response_checksum_validation(ResponseChecksumValidation::WhenRequired);
+        }
+        let region = shared_config.region().map(|region| region.to_string()).unwrap_or_default();
+        // One client per gateway, they only differ by their endpoint
+        let endpoints = EndpointPool::parse_urls(&args.endpoint_url).into_iter()
+            .map(|url| Endpoint {
+                client: S3Client::from_conf(s3_config_builder.clone().endpoint_url(&url).build()),
+                url,
+            })
+            .collect();
+        let endpoints = EndpointPool::new(endpoints, &args.endpoint_selection)?;
+
+        // Result sinks setup
+        let mut sinks: Vec<Box<dyn MetricsSink>> = Vec::new();
+        if let Some(connection) = args.elastic.connection()? {
+            let bulk = BulkConfig {
+                batch_size: args.bulk_size,
+                flush_interval: Self::parse_duration(&args.bulk_interval)?,
+                retries: args.bulk_retries,
+                spill_file: args.spill_file.clone(),
+            };
+            let index = IndexConfig::new(&args.index, args.data_stream)?;
+            let template = args.install_template.then_some(args.template_name.as_str());
+            sinks.push(Box::new(ElasticsearchSink::connect(&connection, bulk, index, template).await?));
+        }
+        if let Some(endpoint) = &args.otlp_endpoint {
+            sinks.push(Box::new(OtlpSink::new(endpoint, &args.bucket_name)?));
+        }
+        for spec in &args.sinks {
+            sinks.push(sink::from_spec(spec)?);
+        }
+
+        let object_size = SizeDistribution::parse(&args.object_size)?;
+        let multipart = match &args.multipart_threshold {
+            Some(threshold) => {
+                let part_size = Self::parse_size(&args.part_size);
+                if !(MIN_PART_SIZE..=MAX_PART_SIZE).contains(&part_size) {
+                    return Err(format!("Invalid part size '{}', S3 accepts 5MB to 5GB", args.part_size).into());
+                }
+                if object_size.max().div_ceil(part_size) > MAX_PARTS {
+                    return Err(format!(
+                        "Objects of up to {} bytes need more than {} parts of {}",
+                        object_size.max(),
+                        MAX_PARTS,
+                        args.part_size
+                    ).into());
+                }
+                Some(MultipartConfig {
+                    threshold: Self::parse_size(threshold),
+                    part_size,
+                    part_concurrency: args.part_concurrency.max(1),
+                })
+            }
+            None => None,
+        };
+        // Objects below the multipart threshold go as a single PUT
+        let max_put = match &multipart {
+            Some(multipart) => object_size.max().min(multipart.threshold.saturating_sub(1)),
+            None => object_size.max(),
+        };
+        if max_put > MAX_PUT_SIZE {
+            return Err(format!(
+                "Objects of up to {} bytes are above the 5GB a single PUT accepts, use --multipart-threshold",
+                max_put
+            ).into());
+        }
+
+        let range_pattern = match &args.range_pattern {
+            Some(spec) => Some(RangePattern::parse(spec, args.ranges_per_object)?),
+            None => None,
+        };
+
+        let payload = Arc::new(Payload::parse(&args.payload, args.verify_seed)?);
+        let checksums = match &args.checksum_algorithm {
+            Some(spec) => Some(ChecksumSplit::parse(spec)?),
+            None => None,
+        };
 
         Ok(Self {
-            s3,
-            elastic,
+            endpoints,
+            sinks,
             args,
-            cleanup_list: Vec::new(),
+            object_size,
+            payload,
+            checksums,
+            encryption,
+            credential_source,
+            region,
+            multipart,
+            range_pattern,
+            metrics,
+            cleanup_list: Mutex::new(Vec::new()),
         })
     }
 
     async fn check_bucket_existence(&self) -> bool {
-        self.s3.head_bucket()
+        self.endpoints.first().client.head_bucket()
             .bucket(&self.args.bucket_name)
             .send()
             .await
@@ -95,7 +626,7 @@ impl ObjectAnalyzer {
     }
 
     async fn create_bucket(&self) -> Result<(), S3Error> {
-        self.s3.create_bucket()
+        self.endpoints.first().client.create_bucket()
             .bucket(&self.args.bucket_name)
             .send()
             .await?;
@@ -107,56 +638,409 @@ impl ObjectAnalyzer {
         (1000.0 / latency_ms) * (size_bytes as f64) / 1_000_000.0
     }
 
-    fn generate_object_name(&self) -> String {
-        if let Some(prefix) = &self.args.prefix {
-            format!("{}/{}", prefix, Uuid::new_v4())
+    fn generate_object_name(&self, worker_id: usize) -> String {
+        // Each worker writes to its own key space when running concurrently
+        let name = if self.args.concurrency > 1 {
+            format!("worker-{}/{}", worker_id, Uuid::new_v4())
         } else {
             Uuid::new_v4().to_string()
+        };
+
+        if let Some(prefix) = &self.args.prefix {
+            format!("{}/{}", prefix, name)
+        } else {
+            name
+        }
+    }
+
+    fn parse_duration(duration_str: &str) -> Result<Duration, BoxError> {
+        // Simple parse duration (supports ms, s, m, h suffixes, plain numbers are seconds)
+        let duration_str = duration_str.trim().to_lowercase();
+        let (value, scale) = if let Some(value) = duration_str.strip_suffix("ms") {
+            (value, 0.001)
+        } else if let Some(value) = duration_str.strip_suffix('s') {
+            (value, 1.0)
+        } else if let Some(value) = duration_str.strip_suffix('m') {
+            (value, 60.0)
+        } else if let Some(value) = duration_str.strip_suffix('h') {
+            (value, 3600.0)
+        } else {
+            (duration_str.as_str(), 1.0)
+        };
+
+        let value: f64 = value.trim().parse()
+            .map_err(|_| format!("Invalid duration '{}'", duration_str))?;
+        // Negative, non-finite and out of range values are all rejected here
+        Duration::try_from_secs_f64(value * scale)
+            .map_err(|_| format!("Invalid duration '{}'", duration_str).into())
+    }
+
+    // Simple parse size (supports K, M, G suffixes)
+    fn parse_size(size_str: &str) -> usize {
+        let size_str = size_str.trim().to_uppercase();
+        if size_str.ends_with("KB") {
+            size_str[..size_str.len()-2].parse::<usize>().unwrap_or(0) * 1024
+        } else if size_str.ends_with("MB") {
+            size_str[..size_str.len()-2].parse::<usize>().unwrap_or(0) * 1024 * 1024
+        } else if size_str.ends_with("GB") {
+            size_str[..size_str.len()-2].parse::<usize>().unwrap_or(0) * 1024 * 1024 * 1024
+        } else {
+            size_str.parse::<usize>().unwrap_or(0)
+        }
+    }
+
+    // Algorithm for the next operation when --checksum-algorithm is given
+    fn pick_checksum<R: Rng>(&self, rng: &mut R) -> Option<ChecksumAlgorithm> {
+        self.checksums.as_ref().and_then(|checksums| checksums.pick(rng))
+    }
+
+    // With --verify the content depends on the key only, so any reader can check it.
+    // Large objects take a while to generate, so this runs off the async threads. Callers
+    // generate before claiming their slot, so generation never shows up in the latencies.
+    async fn object_data(&self, object_name: &str, size: usize) -> Result<Bytes, BoxError> {
+        let payload = self.payload.clone();
+        let keyed = self.args.verify.then(|| payload::keyed_rng(object_name, self.args.verify_seed));
+        let data = tokio::task::spawn_blocking(move || match keyed {
+            Some(mut rng) => payload.generate(size, &mut rng),
+            None => payload.generate(size, &mut rand::rng()),
+        }).await?;
+        Ok(Bytes::from(data))
+    }
+
+    // Content an object of `object_size` bytes was written with, None without --verify
+    async fn expected_data(&self, object_name: &str, object_size: usize) -> Result<Option<Bytes>, BoxError> {
+        if !self.args.verify {
+            return Ok(None);
+        }
+        self.object_data(object_name, object_size).await.map(Some)
+    }
+
+    // With --verify the written length is stored with the object, so readers notice truncation
+    fn size_metadata(&self, size: usize) -> Option<HashMap<String, String>> {
+        self.args.verify.then(|| HashMap::from([(SIZE_METADATA.to_string(), size.to_string())]))
+    }
+
+    fn written_size(metadata: Option<&HashMap<String, String>>) -> Option<usize> {
+        metadata?.get(SIZE_METADATA)?.parse().ok()
+    }
+
+    // Compares what was read with `expected`, the whole object as written. `range` is the
+    // inclusive byte range that was read. An object whose length differs from the one
+    // stored with it was cut short or padded, so it fails whatever the bytes say.
+    fn verify_data(expected: Option<&[u8]>, data: &[u8], range: Option<(u64, u64)>, written_size: Option<usize>) -> Option<bool> {
+        let expected = expected?;
+        if written_size.is_some_and(|written_size| written_size != expected.len()) {
+            return Some(false);
+        }
+
+        let expected = match range {
+            Some((range_start, range_end)) => {
+                let end = (range_end as usize).min(expected.len().saturating_sub(1));
+                expected.get(range_start as usize..=end).unwrap_or_default()
+            }
+            None => expected,
+        };
+        Some(data == expected)
+    }
+
+    async fn put_object(
+        &self,
+        route: &Route<'_>,
+        object_name: &str,
+        bin_data: &Bytes,
+        checksum: Option<ChecksumAlgorithm>,
+    ) -> Result<PhaseTiming, S3Error> {
+        let (body, sent_at) = timed_byte_stream(bin_data.clone());
+
+        let start = Instant::now();
+        route.endpoint.client.put_object()
+            .bucket(&self.args.bucket_name)
+            .key(object_name)
+            .set_checksum_algorithm(checksum)
+            .set_metadata(self.size_metadata(bin_data.len()))
+            .set_server_side_encryption(self.encryption.server_side_encryption())
+            .set_ssekms_key_id(self.encryption.kms_key_id())
+            .set_sse_customer_algorithm(self.encryption.customer_algorithm())
+            .set_sse_customer_key(self.encryption.customer_key())
+            .set_sse_customer_key_md5(self.encryption.customer_key_md5())
+            .body(body)
+            .customize()
+            .interceptor(route.attempts.clone())
+            .send()
+            .await?;
+        let end = Instant::now();
+
+        // An empty body is never polled, so all of the call was spent waiting
+        let sent_at = sent_at.lock().unwrap().unwrap_or(start);
+        Ok(PhaseTiming {
+            ttfb: end.saturating_duration_since(sent_at),
+            transfer_time: sent_at.saturating_duration_since(start),
+        })
+    }
+
+    // Uploads the object, switching to multipart above the configured threshold.
+    // Returns the per-part results of a multipart upload, empty for a single PUT,
+    // and the TTFB split of a single PUT.
+    async fn upload_object(
+        &self,
+        route: &Route<'_>,
+        object_name: &str,
+        bin_data: &Bytes,
+        checksum: Option<ChecksumAlgorithm>,
+    ) -> Result<(Vec<OpResult>, Option<PhaseTiming>), BoxError> {
+        // An upload without parts cannot be completed, empty objects always go as a single PUT
+        let uploaded = match &self.multipart {
+            Some(multipart) if !bin_data.is_empty() && bin_data.len() >= multipart.threshold => {
+                (self.put_object_multipart(route, multipart, object_name, bin_data, checksum).await?, None)
+            }
+            _ => (Vec::new(), Some(self.put_object(route, object_name, bin_data, checksum).await?)),
+        };
+        self.cleanup_list.lock().unwrap().push(object_name.to_string());
+        Ok(uploaded)
+    }
+
+    async fn put_object_multipart(
+        &self,
+        route: &Route<'_>,
+        multipart: &MultipartConfig,
+        object_name: &str,
+        bin_data: &Bytes,
+        checksum: Option<ChecksumAlgorithm>,
+    ) -> Result<Vec<OpResult>, BoxError> {
+        let upload = route.endpoint.client.create_multipart_upload()
+            .bucket(&self.args.bucket_name)
+            .key(object_name)
+            .set_checksum_algorithm(checksum.clone())
+            .set_metadata(self.size_metadata(bin_data.len()))
+            .set_server_side_encryption(self.encryption.server_side_encryption())
+            .set_ssekms_key_id(self.encryption.kms_key_id())
+            .set_sse_customer_algorithm(self.encryption.customer_algorithm())
+            .set_sse_customer_key(self.encryption.customer_key())
+            .set_sse_customer_key_md5(self.encryption.customer_key_md5())
+            .customize()
+            .interceptor(route.attempts.clone())
+            .send()
+            .await?;
+        let upload_id = upload.upload_id()
+            .ok_or("CreateMultipartUpload returned no upload id")?
+            .to_string();
+
+        let completed = match self.upload_parts(route, multipart, object_name, &upload_id, bin_data, checksum).await {
+            Ok((completed, parts)) => {
+                let completed = route.endpoint.client.complete_multipart_upload()
+                    .bucket(&self.args.bucket_name)
+                    .key(object_name)
+                    .upload_id(&upload_id)
+                    .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(completed)).build())
+                    .set_sse_customer_algorithm(self.encryption.customer_algorithm())
+                    .set_sse_customer_key(self.encryption.customer_key())
+                    .set_sse_customer_key_md5(self.encryption.customer_key_md5())
+                    .customize()
+                    .interceptor(route.attempts.clone())
+                    .send()
+                    .await;
+                completed.map(|_| parts).map_err(BoxError::from)
+            }
+            Err(e) => Err(e),
+        };
+
+        if completed.is_err() {
+            // Don't leave orphaned parts behind, the original error is what we report
+            let _ = route.endpoint.client.abort_multipart_upload()
+                .bucket(&self.args.bucket_name)
+                .key(object_name)
+                .upload_id(&upload_id)
+                .send()
+                .await;
         }
+        completed
     }
 
-    fn create_bin_data(&self) -> Vec<u8> {
-        // Simple parse size (supports K, M, G suffixes)
-        fn parse_size(size_str: &str) -> usize {
-            let size_str = size_str.trim().to_uppercase();
-            if size_str.ends_with("KB") {
-                size_str[..size_str.len()-2].parse::<usize>().unwrap_or(0) * 1024
-            } else if size_str.ends_with("MB") {
-                size_str[..size_str.len()-2].parse::<usize>().unwrap_or(0) * 1024 * 1024
-            } else if size_str.ends_with("GB") {
-                size_str[..size_str.len()-2].parse::<usize>().unwrap_or(0) * 1024 * 1024 * 1024
-            } else {
-                size_str.parse::<usize>().unwrap_or(0)
+    async fn upload_parts(
+        &self,
+        route: &Route<'_>,
+        multipart: &MultipartConfig,
+        object_name: &str,
+        upload_id: &str,
+        bin_data: &Bytes,
+        checksum: Option<ChecksumAlgorithm>,
+    ) -> Result<(Vec<CompletedPart>, Vec<OpResult>), BoxError> {
+        let mut tasks = JoinSet::new();
+        let mut uploaded = Vec::new();
+
+        // Parts are slices of the object's buffer, not copies
+        for (index, offset) in (0..bin_data.len()).step_by(multipart.part_size).enumerate() {
+            let chunk = bin_data.slice(offset..(offset + multipart.part_size).min(bin_data.len()));
+            // Keep at most part_concurrency parts in flight
+            if tasks.len() >= multipart.part_concurrency
+                && let Some(part) = tasks.join_next().await
+            {
+                uploaded.push(part??);
             }
+
+            let part_number = index as i32 + 1;
+            let part_size = chunk.len();
+            // Retries are counted for the part alone and for the whole object
+            let part_attempts = Attempts::default();
+            let request = route.endpoint.client.upload_part()
+                .bucket(&self.args.bucket_name)
+                .key(object_name)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .set_checksum_algorithm(checksum.clone())
+                .set_sse_customer_algorithm(self.encryption.customer_algorithm())
+                .set_sse_customer_key(self.encryption.customer_key())
+                .set_sse_customer_key_md5(self.encryption.customer_key_md5())
+                .body(ByteStream::from(chunk))
+                .customize()
+                .interceptor(route.attempts.clone())
+                .interceptor(part_attempts.clone());
+
+            tasks.spawn(async move {
+                let start = Instant::now();
+                let resp = request.send().await?;
+                let timing = OpTiming::measure(start, start);
+                let completed = checksum::completed_part(part_number, &resp);
+                Ok::<_, BoxError>((part_number, part_size, completed, timing, part_attempts.retries()))
+            });
+        }
+        while let Some(part) = tasks.join_next().await {
+            uploaded.push(part??);
         }
+        uploaded.sort_by_key(|(part_number, ..)| *part_number);
 
-        let size = parse_size(&self.args.object_size);
-        vec![b'a'; size]
+        let mut completed = Vec::with_capacity(uploaded.len());
+        let mut parts = Vec::with_capacity(uploaded.len());
+        for (part_number, part_size, completed_part, timing, retries) in uploaded {
+            completed.push(completed_part);
+            let mut part = OpResult::new(Operation::UploadPart, object_name.to_string(), part_size, timing);
+            part.part_number = Some(part_number);
+            part.checksum = checksum.clone();
+            part.endpoint = Some(route.endpoint.url.clone());
+            part.retries = Some(retries);
+            parts.push(part);
+        }
+        Ok((completed, parts))
     }
 
-    async fn put_object(&mut self, object_name: &str, bin_data: &[u8]) -> Result<(), S3Error> {
-        self.s3.put_object()
+    // With `validate` the SDK checks the body against the checksum stored with the object,
+    // the algorithm it used is returned along with the length the object was written with
+    async fn get_object(
+        &self,
+        route: &Route<'_>,
+        object_name: &str,
+        validate: bool,
+    ) -> Result<(Bytes, PhaseTiming, Option<ChecksumAlgorithm>, Option<usize>), BoxError> {
+        let start = Instant::now();
+        let resp = route.endpoint.client.get_object()
             .bucket(&self.args.bucket_name)
             .key(object_name)
-            .body(ByteStream::from(bin_data.to_vec()))
+            .set_checksum_mode(validate.then_some(ChecksumMode::Enabled))
+            .set_sse_customer_algorithm(self.encryption.customer_algorithm())
+            .set_sse_customer_key(self.encryption.customer_key())
+            .set_sse_customer_key_md5(self.encryption.customer_key_md5())
+            .customize()
+            .interceptor(route.attempts.clone())
             .send()
             .await?;
-        self.cleanup_list.push(object_name.to_string());
-        Ok(())
+        let checksum = checksum::validated_with(&resp);
+        let written_size = Self::written_size(resp.metadata());
+        let headers_at = Instant::now();
+
+        let data = resp.body.collect().await?;
+        let phases = PhaseTiming {
+            ttfb: headers_at - start,
+            transfer_time: headers_at.elapsed(),
+        };
+        Ok((data.into_bytes(), phases, checksum, written_size))
     }
 
-    async fn get_object(&self, object_name: &str) -> Result<(), S3Error> {
-        let resp = self.s3.get_object()
+    async fn get_object_range(
+        &self,
+        route: &Route<'_>,
+        object_name: &str,
+        range_start: u64,
+        range_end: u64,
+    ) -> Result<(Bytes, PhaseTiming), BoxError> {
+        let start = Instant::now();
+        let resp = route.endpoint.client.get_object()
             .bucket(&self.args.bucket_name)
             .key(object_name)
+            .range(format!("bytes={}-{}", range_start, range_end))
+            .set_sse_customer_algorithm(self.encryption.customer_algorithm())
+            .set_sse_customer_key(self.encryption.customer_key())
+            .set_sse_customer_key_md5(self.encryption.customer_key_md5())
+            .customize()
+            .interceptor(route.attempts.clone())
             .send()
             .await?;
+        let headers_at = Instant::now();
 
         let data = resp.body.collect().await?;
-        // We can do something with data if needed
+        let phases = PhaseTiming {
+            ttfb: headers_at - start,
+            transfer_time: headers_at.elapsed(),
+        };
+        Ok((data.into_bytes(), phases))
+    }
+
+    // Length of the object and the one it was written with
+    async fn head_object(&self, route: &Route<'_>, object_name: &str) -> Result<(u64, Option<usize>), S3Error> {
+        let resp = route.endpoint.client.head_object()
+            .bucket(&self.args.bucket_name)
+            .key(object_name)
+            .set_sse_customer_algorithm(self.encryption.customer_algorithm())
+            .set_sse_customer_key(self.encryption.customer_key())
+            .set_sse_customer_key_md5(self.encryption.customer_key_md5())
+            .customize()
+            .interceptor(route.attempts.clone())
+            .send()
+            .await?;
+        Ok((resp.content_length().unwrap_or(0).max(0) as u64, Self::written_size(resp.metadata())))
+    }
+
+    async fn delete_object(&self, route: &Route<'_>, object_name: &str) -> Result<(), S3Error> {
+        route.endpoint.client.delete_object()
+            .bucket(&self.args.bucket_name)
+            .key(object_name)
+            .customize()
+            .interceptor(route.attempts.clone())
+            .send()
+            .await?;
         Ok(())
     }
 
+    async fn list_random_objects(&self, count: usize) -> Result<Vec<String>, S3Error> {
+        // Reservoir sampling over all pages, so we never hold more than `count` keys
+        let mut rng = StdRng::from_os_rng();
+        let mut sample: Vec<String> = Vec::with_capacity(count);
+        let mut seen: usize = 0;
+
+        let mut pages = self.endpoints.first().client.list_objects_v2()
+            .bucket(&self.args.bucket_name)
+            .set_prefix(self.args.prefix.clone())
+            .into_paginator()
+            .send();
+
+        while let Some(page) = pages.next().await {
+            let page = page?;
+            for key in page.contents().iter().filter_map(|obj| obj.key()) {
+                seen += 1;
+                if sample.len() < count {
+                    sample.push(key.to_string());
+                } else {
+                    let slot = rng.random_range(0..seen);
+                    if slot < count {
+                        sample[slot] = key.to_string();
+                    }
+                }
+            }
+        }
+
+        sample.shuffle(&mut rng);
+        Ok(sample)
+    }
+
     fn evaluate_latency(&self, duration_ms: f64) -> bool {
         if let Some(max) = self.args.max_latency {
             duration_ms > max
@@ -169,67 +1053,509 @@ impl ObjectAnalyzer {
         Utc::now().timestamp_millis()
     }
 
-    async fn write_elastic_data(&self, data: serde_json::Value) -> Result<(), Box<dyn Error>> {
-        self.elastic.index(elasticsearch::IndexParts::Index("s3-perf-index"))
-            .body(data)
-            .send()
-            .await?;
+    fn create_document(&self, result: &OpResult, source: &str, worker_id: usize) -> serde_json::Value {
+        let service_time_ms = result.timing.service_time.as_secs_f64() * 1000.0;
+        let response_time_ms = result.timing.response_time.as_secs_f64() * 1000.0;
+        let exceeded = self.evaluate_latency(response_time_ms);
+        let throughput = Self::calculate_throughput(service_time_ms, result.size_bytes);
+
+        serde_json::json!({
+            "latency": response_time_ms,
+            "service_time": service_time_ms,
+            "response_time": response_time_ms,
+            "ttfb": result.phases.as_ref().map(|phases| phases.ttfb.as_secs_f64() * 1000.0),
+            "transfer_time": result.phases.as_ref().map(|phases| phases.transfer_time.as_secs_f64() * 1000.0),
+            "latency_exceeded": exceeded,
+            "timestamp": Self::create_timestamp(),
+            "workload": self.args.workload,
+            "phase": self.args.phase,
+            "operation": result.operation.as_str(),
+            "size": self.args.object_size,
+            "payload": self.args.payload,
+            "size_in_bytes": result.size_bytes,
+            "throughput": throughput,
+            "object_name": result.object_name,
+            "multipart": result.parts.is_some() || result.part_number.is_some(),
+            "part_number": result.part_number,
+            "parts": result.parts,
+            "range_start": result.range.map(|(start, _)| start),
+            "range_end": result.range.map(|(_, end)| end),
+            "verified": result.verified,
+            "checksum_algorithm": result.checksum.as_ref().map(checksum::name),
+            "endpoint": result.endpoint,
+            "retries": result.retries,
+            "encryption": self.encryption.name(),
+            "credentials": self.credential_source,
+            "region": self.region,
+            "signing_name": self.args.signing_name.as_deref().unwrap_or("s3"),
+            "addressing": if self.args.force_path_style { "path" } else { "virtual-host" },
+            "tls": if self.args.insecure { "insecure" } else if self.args.ca_cert.is_some() { "custom-ca" } else { "default" },
+            "error": result.error.as_deref().or((result.verified == Some(false)).then_some("checksum_mismatch")),
+            "source": source,
+            "worker": worker_id,
+            "concurrency": self.args.concurrency,
+            "rate": self.args.rate,
+        })
+    }
+
+    async fn write_document(&self, doc: serde_json::Value) -> Result<(), BoxError> {
+        for sink in &self.sinks {
+            sink.write(&doc).await
+                .map_err(|e| format!("Writing to {} sink failed: {}", sink.name(), e))?;
+        }
+        Ok(())
+    }
+
+    async fn flush_sinks(&self) -> Result<(), BoxError> {
+        for sink in &self.sinks {
+            sink.flush().await
+                .map_err(|e| format!("Flushing {} sink failed: {}", sink.name(), e))?;
+        }
+        Ok(())
+    }
+
+    async fn record_result(
+        &self,
+        ctx: &RunContext,
+        worker_id: usize,
+        result: OpResult,
+        stats: &mut WorkerStats,
+    ) -> Result<(), BoxError> {
+        stats.record(result.operation, result.timing.response_time, result.size_bytes);
+        // A corrupted read still completed, it is timed like any other and counted apart from errors
+        let mismatch = result.verified == Some(false);
+        if mismatch {
+            eprintln!("{} {} failed: checksum mismatch", result.operation.as_str(), result.object_name);
+            stats.record_mismatch(result.operation);
+        }
+        if let Some(metrics) = &self.metrics {
+            let status = if mismatch { "mismatch" } else { "ok" };
+            let endpoint = result.endpoint.as_deref().unwrap_or_default();
+            metrics.observe(result.operation, status, &self.args.bucket_name, endpoint, result.timing.response_time, result.size_bytes);
+        }
+
+        let doc = self.create_document(&result, &ctx.source, worker_id);
+        self.write_document(doc).await
+    }
+
+    // Part documents are indexed alongside the whole-object one, the summary keeps them apart
+    async fn record_parts(
+        &self,
+        ctx: &RunContext,
+        worker_id: usize,
+        parts: Vec<OpResult>,
+        stats: &mut WorkerStats,
+    ) -> Result<(), BoxError> {
+        for part in parts {
+            self.record_result(ctx, worker_id, part, stats).await?;
+        }
         Ok(())
     }
 
-    async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+    // A failed operation is counted, reported and recorded with its error, the run goes on
+    async fn record_error(
+        &self,
+        ctx: &RunContext,
+        worker_id: usize,
+        mut result: OpResult,
+        error: BoxError,
+        stats: &mut WorkerStats,
+    ) -> Result<(), BoxError> {
+        // The SDK's own message is only the error kind, the cause is further down the chain
+        let error = DisplayErrorContext(&*error).to_string();
+        let endpoint = result.endpoint.as_deref().unwrap_or_default();
+        eprintln!("{} {} on {} failed: {}", result.operation.as_str(), result.object_name, endpoint, error);
+        stats.record_error(result.operation);
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_error(result.operation, &self.args.bucket_name, endpoint);
+        }
+
+        result.error = Some(error);
+        let doc = self.create_document(&result, &ctx.source, worker_id);
+        self.write_document(doc).await
+    }
+
+    async fn write_worker(
+        self: Arc<Self>,
+        worker_id: usize,
+        ctx: Arc<RunContext>,
+    ) -> Result<WorkerStats, BoxError> {
+        let mut stats = WorkerStats::default();
+        loop {
+            // Generated before the slot is claimed, so it never counts as store latency
+            let object_name = self.generate_object_name(worker_id);
+            let size = self.object_size.sample(&mut rand::rng());
+            let data = self.object_data(&object_name, size).await?;
+            let Some((_, scheduled)) = ctx.next_op().await else {
+                break;
+            };
+            let checksum = self.pick_checksum(&mut rand::rng());
+            let route = self.endpoints.route();
+
+            let start = Instant::now();
+            let (parts, phases) = match self.upload_object(&route, &object_name, &data, checksum.clone()).await {
+                Ok(uploaded) => uploaded,
+                Err(e) => {
+                    let mut result = OpResult::new(Operation::Put, object_name, 0, OpTiming::measure(scheduled, start));
+                    result.checksum = checksum;
+                    result.set_route(&route);
+                    self.record_error(&ctx, worker_id, result, e, &mut stats).await?;
+                    continue;
+                }
+            };
+            let timing = OpTiming::measure(scheduled, start);
+
+            let mut result = OpResult::new(Operation::Put, object_name, size, timing);
+            result.phases = phases;
+            result.checksum = checksum;
+            result.parts = (!parts.is_empty()).then_some(parts.len());
+            result.set_route(&route);
+            self.record_parts(&ctx, worker_id, parts, &mut stats).await?;
+            self.record_result(&ctx, worker_id, result, &mut stats).await?;
+        }
+        Ok(stats)
+    }
+
+    async fn read_worker(
+        self: Arc<Self>,
+        worker_id: usize,
+        ctx: Arc<RunContext>,
+        object_names: Arc<Vec<String>>,
+    ) -> Result<WorkerStats, BoxError> {
+        let mut stats = WorkerStats::default();
+        if object_names.is_empty() {
+            return Ok(stats);
+        }
+
+        // Duration-bounded runs cycle over the sampled keys
+        while let Some((index, scheduled)) = ctx.next_op().await {
+            let object_name = &object_names[index % object_names.len()];
+            if let Some(pattern) = &self.range_pattern {
+                self.read_ranges(&ctx, worker_id, pattern, object_name, scheduled, &mut stats).await?;
+                continue;
+            }
+
+            let route = self.endpoints.route();
+            let validate = self.pick_checksum(&mut rand::rng()).is_some();
+            let start = Instant::now();
+            let (data, phases, checksum, written_size) = match self.get_object(&route, object_name, validate).await {
+                Ok(downloaded) => downloaded,
+                Err(e) => {
+                    let mut result = OpResult::new(Operation::Get, object_name.clone(), 0, OpTiming::measure(scheduled, start));
+                    result.set_route(&route);
+                    self.record_error(&ctx, worker_id, result, e, &mut stats).await?;
+                    continue;
+                }
+            };
+            let timing = OpTiming::measure(scheduled, start);
+
+            let mut result = OpResult::new(Operation::Get, object_name.clone(), data.len(), timing);
+            result.phases = Some(phases);
+            let expected = self.expected_data(object_name, data.len()).await?;
+            result.verified = Self::verify_data(expected.as_deref(), &data, None, written_size);
+            result.checksum = checksum;
+            result.set_route(&route);
+            self.record_result(&ctx, worker_id, result, &mut stats).await?;
+        }
+        Ok(stats)
+    }
+
+    // Reads one object with the configured range pattern, one document per range
+    async fn read_ranges(
+        &self,
+        ctx: &RunContext,
+        worker_id: usize,
+        pattern: &RangePattern,
+        object_name: &str,
+        scheduled: Instant,
+        stats: &mut WorkerStats,
+    ) -> Result<(), BoxError> {
+        // All ranges of the object go through the same endpoint. Readers look up the
+        // object length before planning them, this is not measured: the first range's
+        // slot moves forward by the time the lookup took.
+        let head = self.endpoints.route();
+        let start = Instant::now();
+        let (object_size, written_size) = match self.head_object(&head, object_name).await {
+            Ok(sizes) => sizes,
+            Err(e) => {
+                let mut result = OpResult::new(Operation::GetRange, object_name.to_string(), 0, OpTiming::measure(scheduled, start));
+                result.set_route(&head);
+                return self.record_error(ctx, worker_id, result, e.into(), stats).await;
+            }
+        };
+        let scheduled = scheduled + start.elapsed();
+        let ranges = pattern.plan(object_size, &mut rand::rng());
+
+        // Only the first range can have waited for its slot, the others start right away
+        let mut scheduled = Some(scheduled);
+        let mut completed = Vec::new();
+        for (range_start, range_end) in ranges {
+            let route = Route { endpoint: head.endpoint, attempts: Attempts::default() };
+            let start = Instant::now();
+            let downloaded = self.get_object_range(&route, object_name, range_start, range_end).await;
+            let timing = OpTiming::measure(scheduled.take().unwrap_or(start), start);
+            let (data, phases) = match downloaded {
+                Ok(downloaded) => downloaded,
+                Err(e) => {
+                    let mut result = OpResult::new(Operation::GetRange, object_name.to_string(), 0, timing);
+                    result.range = Some((range_start, range_end));
+                    result.set_route(&route);
+                    self.record_error(ctx, worker_id, result, e, stats).await?;
+                    continue;
+                }
+            };
+
+            let mut result = OpResult::new(Operation::GetRange, object_name.to_string(), data.len(), timing);
+            result.phases = Some(phases);
+            result.range = Some((range_start, range_end));
+            result.set_route(&route);
+            completed.push((result, data));
+        }
+
+        // The expected content is generated once all ranges are timed, and only once for all of them
+        if completed.is_empty() {
+            return Ok(());
+        }
+        let expected = self.expected_data(object_name, object_size as usize).await?;
+        for (mut result, data) in completed {
+            result.verified = Self::verify_data(expected.as_deref(), &data, result.range, written_size);
+            self.record_result(ctx, worker_id, result, stats).await?;
+        }
+        Ok(())
+    }
+
+    async fn mixed_worker(
+        self: Arc<Self>,
+        worker_id: usize,
+        ctx: Arc<RunContext>,
+        mix: Arc<OperationMix>,
+        keys: Arc<KeySet>,
+    ) -> Result<WorkerStats, BoxError> {
+        let mut rng = StdRng::from_os_rng();
+        let mut stats = WorkerStats::default();
+        // Content of the next PUT, generated before a slot is claimed so it never counts
+        // as store latency
+        let mut next_put = None;
+        loop {
+            if next_put.is_none() && mix.includes(Operation::Put) {
+                let object_name = self.generate_object_name(worker_id);
+                let size = self.object_size.sample(&mut rng);
+                let data = self.object_data(&object_name, size).await?;
+                next_put = Some((object_name, data));
+            }
+            let Some((_, scheduled)) = ctx.next_op().await else {
+                break;
+            };
+
+            let mut operation = mix.pick(&mut rng);
+            let existing = match operation {
+                Operation::Put | Operation::UploadPart => None,
+                Operation::Delete => keys.take_random(&mut rng),
+                Operation::Get | Operation::Head | Operation::GetRange => keys.random(&mut rng),
+            };
+            // Nothing to read or delete yet, so grow the key set instead. A mix without
+            // puts never writes, once its keys are gone the worker is done.
+            let (object_name, data) = match existing {
+                Some(object_name) => (object_name, Bytes::new()),
+                None => match next_put.take() {
+                    Some(put) => {
+                        operation = Operation::Put;
+                        put
+                    }
+                    None => {
+                        println!("Worker {} stopped, no objects left to {}", worker_id, operation.as_str());
+                        break;
+                    }
+                },
+            };
+
+            let mut checksum = self.pick_checksum(&mut rng);
+            let route = self.endpoints.route();
+            let mut parts = Vec::new();
+            let mut downloaded = None;
+            let start = Instant::now();
+            let outcome: Result<(usize, Option<PhaseTiming>), BoxError> = match operation {
+                Operation::Put | Operation::UploadPart => self.upload_object(&route, &object_name, &data, checksum.clone()).await
+                    .map(|(uploaded, phases)| {
+                        parts = uploaded;
+                        (data.len(), phases)
+                    }),
+                Operation::Get | Operation::GetRange => self.get_object(&route, &object_name, checksum.is_some()).await
+                    .map(|(data, phases, validated_with, written_size)| {
+                        let size_bytes = data.len();
+                        downloaded = Some((data, written_size));
+                        checksum = validated_with;
+                        (size_bytes, Some(phases))
+                    }),
+                Operation::Head => self.head_object(&route, &object_name).await
+                    .map(|_| (0, None))
+                    .map_err(BoxError::from),
+                Operation::Delete => self.delete_object(&route, &object_name).await
+                    .map(|_| (0, None))
+                    .map_err(BoxError::from),
+            };
+            let timing = OpTiming::measure(scheduled, start);
+            let (size_bytes, phases) = match outcome {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    let mut result = OpResult::new(operation, object_name, 0, timing);
+                    result.set_route(&route);
+                    self.record_error(&ctx, worker_id, result, e, &mut stats).await?;
+                    continue;
+                }
+            };
+
+            if operation == Operation::Put {
+                keys.insert(object_name.clone());
+            }
+
+            let mut result = OpResult::new(operation, object_name, size_bytes, timing);
+            result.phases = phases;
+            result.parts = (!parts.is_empty()).then_some(parts.len());
+            result.set_route(&route);
+            if matches!(operation, Operation::Put | Operation::Get) {
+                result.checksum = checksum;
+            }
+            if let Some((data, written_size)) = downloaded {
+                let expected = self.expected_data(&result.object_name, data.len()).await?;
+                result.verified = Self::verify_data(expected.as_deref(), &data, None, written_size);
+            }
+            self.record_parts(&ctx, worker_id, parts, &mut stats).await?;
+            self.record_result(&ctx, worker_id, result, &mut stats).await?;
+        }
+        Ok(stats)
+    }
+
+    async fn run(self: Arc<Self>) -> Result<(), BoxError> {
+        let workload = Workload::parse(&self.args.workload)?;
+
+        let duration = match &self.args.duration {
+            Some(duration) => Some(Self::parse_duration(duration)?),
+            None => None,
+        };
+        if duration.is_none() && self.args.num_objects.is_none() {
+            return Err("Either --num-objects or --duration must be given".into());
+        }
+
         // Check bucket and create if needed
         let exists = self.check_bucket_existence().await;
-        if !exists && self.args.workload.to_lowercase() == "write" {
+        if !exists && workload.writes() {
             self.create_bucket().await?;
         }
 
-        let data = self.create_bin_data();
-
         let source = format!("{}{}", hostname::get()?.to_string_lossy(), Uuid::new_v4());
 
-        if self.args.workload.to_lowercase() == "write" {
-            for _ in 0..self.args.num_objects {
-                let object_name = self.generate_object_name();
-
-                let start = Instant::now();
-                self.put_object(&object_name, &data).await?;
-                let duration = start.elapsed();
-                let duration_ms = duration.as_secs_f64() * 1000.0;
+        let mut max_ops = self.args.num_objects;
+        let object_names = if !matches!(workload, Workload::Write) {
+            let sample_size = self.args.num_objects.unwrap_or(DEFAULT_READ_SAMPLE);
+            let object_names = self.list_random_objects(sample_size).await?;
+            if object_names.len() < sample_size && matches!(workload, Workload::Read) {
+                println!(
+                    "Only {} objects found in bucket {}, reading all of them",
+                    object_names.len(),
+                    self.args.bucket_name
+                );
+                // Without a deadline each sampled key is read exactly once
+                if duration.is_none() {
+                    max_ops = Some(object_names.len());
+                }
+            }
+            object_names
+        } else {
+            Vec::new()
+        };
 
-                let exceeded = self.evaluate_latency(duration_ms);
+        // The schedule starts ticking here, so bucket setup and listing are not part of the run
+        let schedule = match self.args.rate {
+            Some(rate) => Some(Schedule::new(rate, Arrival::parse(&self.args.arrival)?)?),
+            None => None,
+        };
+        let started = Instant::now();
+        let ctx = Arc::new(RunContext {
+            issued: AtomicUsize::new(0),
+            source,
+            schedule,
+            max_ops,
+            deadline: duration.map(|duration| started + duration),
+        });
 
-                let size_bytes = data.len();
+        let concurrency = self.args.concurrency.max(1);
+        let mut handles = Vec::with_capacity(concurrency);
 
-                let throughput = Self::calculate_throughput(duration_ms, size_bytes);
+        match workload {
+            Workload::Write => {
+                for worker_id in 0..concurrency {
+                    let worker = Arc::clone(&self).write_worker(worker_id, Arc::clone(&ctx));
+                    handles.push(tokio::spawn(worker));
+                }
+            }
+            Workload::Read => {
+                let object_names = Arc::new(object_names);
+                for worker_id in 0..concurrency {
+                    let worker = Arc::clone(&self).read_worker(worker_id, Arc::clone(&ctx), Arc::clone(&object_names));
+                    handles.push(tokio::spawn(worker));
+                }
+            }
+            Workload::Mixed(mix) => {
+                let mix = Arc::new(mix);
+                let keys = Arc::new(KeySet::new(object_names));
+                for worker_id in 0..concurrency {
+                    let worker = Arc::clone(&self).mixed_worker(
+                        worker_id,
+                        Arc::clone(&ctx),
+                        Arc::clone(&mix),
+                        Arc::clone(&keys),
+                    );
+                    handles.push(tokio::spawn(worker));
+                }
+            }
+        }
 
-                let doc = serde_json::json!({
-                    "latency": duration_ms,
-                    "latency_exceeded": exceeded,
-                    "timestamp": Self::create_timestamp(),
-                    "workload": self.args.workload,
-                    "size": self.args.object_size,
-                    "size_in_bytes": size_bytes,
-                    "throughput": throughput,
-                    "object_name": object_name,
-                    "source": source,
-                });
-                self.write_elastic_data(doc).await?;
+        // Every worker is joined and the sinks flushed even when one failed,
+        // so buffered documents are not lost. The first error is reported then.
+        let mut total = WorkerStats::default();
+        let mut failure: Option<BoxError> = None;
+        for handle in handles {
+            match handle.await {
+                Ok(Ok(stats)) => total.merge(stats),
+                Ok(Err(e)) => {
+                    failure.get_or_insert(e);
+                }
+                Err(e) => {
+                    failure.get_or_insert(e.into());
+                }
             }
-        } else if self.args.workload.to_lowercase() == "read" {
-            // TODO: implement list_random_objects method and shuffle/pagination as in Python
-            // For now, just print warning:
-            println!("Read workload not implemented yet");
+        }
+        // Aggregate throughput is measured against wall clock time, not summed per-op rates
+        let summary = total.summarize(started.elapsed());
+        let flushed = self.flush_sinks().await;
+        if let Some(e) = failure {
+            if let Err(flush_error) = flushed {
+                eprintln!("{}", flush_error);
+            }
+            return Err(e);
+        }
+        flushed?;
+
+        println!("Completed run with {} workers", concurrency);
+        if let Some(duration) = duration {
+            println!(
+                "Finished {} operations within the {:.2}s run window",
+                summary.ops,
+                duration.as_secs_f64()
+            );
+        }
+        summary.print();
+
+        if let Some(summary_file) = &self.args.summary_file {
+            std::fs::write(summary_file, serde_json::to_string_pretty(&summary)?)?;
         }
 
         if let Some(cleanup) = &self.args.cleanup {
             if cleanup.to_lowercase() == "yes" {
-                for key in &self.cleanup_list {
-                    self.s3.delete_object()
-                        .bucket(&self.args.bucket_name)
-                        .key(key)
-                        .send()
-                        .await?;
+                let cleanup_list = std::mem::take(&mut *self.cleanup_list.lock().unwrap());
+                for key in &cleanup_list {
+                    self.delete_object(&self.endpoints.route(), key).await?;
                 }
             }
         }
@@ -238,11 +1564,93 @@ impl ObjectAnalyzer {
     }
 }
 
+// The endpoint outlives the phases of a scenario, so it is started once here
+async fn serve_metrics(args: &Args) -> Result<Option<Arc<PrometheusMetrics>>, BoxError> {
+    match &args.metrics_listen {
+        Some(addr) => {
+            let metrics = Arc::new(PrometheusMetrics::new()?);
+            Arc::clone(&metrics).serve(addr).await?;
+            Ok(Some(metrics))
+        }
+        None => Ok(None),
+    }
+}
+
+// Runs the phases of a scenario file one after the other, stopping at the first failure
+async fn run_scenario(path: &str, cli: &[String]) -> Result<(), BoxError> {
+    let scenario = Scenario::load(path)?;
+    let mut metrics = None;
+    // Each encryption setup is parsed once, so a later phase can read what an earlier one
+    // wrote with a generated SSE-C key
+    let mut encryptions: HashMap<(String, Option<String>, Option<String>), Encryption> = HashMap::new();
+
+    // Every phase is checked before the first one runs, a mistake in a late phase
+    // shouldn't surface hours into the scenario
+    let mut phases = Vec::with_capacity(scenario.phases.len());
+    for phase in &scenario.phases {
+        let args = Args::try_parse_from(scenario.phase_args(phase, cli)?)
+            .map_err(|e| format!("Phase {}: {}", phase.name, e))?;
+        args.validate().map_err(|e| format!("Phase {}: {}", phase.name, e))?;
+
+        let setup = (args.sse.trim().to_lowercase(), args.sse_kms_key_id.clone(), args.sse_c_key_file.clone());
+        let encryption = match encryptions.entry(setup) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => entry.insert(args.encryption()?).clone(),
+        };
+        phases.push((phase, args, encryption));
+    }
+
+    for (i, (phase, args, encryption)) in phases.into_iter().enumerate() {
+        if metrics.is_none() {
+            metrics = serve_metrics(&args).await?;
+        }
+
+        println!("Phase {}/{}: {}", i + 1, scenario.phases.len(), phase.name);
+        let analyzer = Arc::new(ObjectAnalyzer::new(args, encryption, metrics.clone()).await?);
+        analyzer.run().await
+            .map_err(|e| format!("Phase {} failed: {}", phase.name, e))?;
+    }
+    Ok(())
+}
+
+async fn replay(args: ReplayArgs) -> Result<(), BoxError> {
+    let bulk = BulkConfig {
+        batch_size: args.bulk_size,
+        flush_interval: Duration::ZERO,
+        retries: args.bulk_retries,
+        // Never append to the file being replayed
+        spill_file: format!("{}.failed", args.file),
+    };
+    let connection = args.elastic.connection()?
+        .ok_or("replay needs --elastic-url or --elastic-cloud-id")?;
+    let index = IndexConfig::new(&args.index, args.data_stream)?;
+    let indexed = sink::replay(&connection, &args.file, bulk, index).await?;
+    println!("Indexed {} documents from {}", indexed, args.file);
+    Ok(())
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+async fn main() -> Result<(), BoxError> {
+    // Scenario phases bring the required flags, so the command line alone may lack them
+    let cli: Vec<String> = std::env::args().collect();
+    if let Some(path) = scenario::path_from_args(&cli) {
+        return run_scenario(&path, &cli).await;
+    }
+
+    let matches = Command::augment_subcommands(Args::command())
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .get_matches();
+    if matches.subcommand_name().is_some() {
+        match Command::from_arg_matches(&matches).unwrap_or_else(|e| e.exit()) {
+            Command::Replay(args) => return replay(args).await,
+        }
+    }
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let metrics = serve_metrics(&args).await?;
+    let encryption = args.encryption()?;
 
-    let mut analyzer = ObjectAnalyzer::new(args).await?;
+    let analyzer = Arc::new(ObjectAnalyzer::new(args, encryption, metrics).await?);
     analyzer.run().await?;
 
     Ok(())
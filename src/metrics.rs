@@ -0,0 +1,114 @@
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::BoxError;
+use crate::workload::Operation;
+
+// Live counters and latency histograms scraped by Prometheus while the run is going
+pub struct PrometheusMetrics {
+    registry: Registry,
+    operations: IntCounterVec,
+    bytes: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl PrometheusMetrics {
+    pub fn new() -> Result<Self, BoxError> {
+        let operations = IntCounterVec::new(
+            Opts::new("s3newbench_operations_total", "S3 operations by outcome and the endpoint they went to"),
+            &["operation", "status", "bucket", "endpoint"],
+        )?;
+        let bytes = IntCounterVec::new(
+            Opts::new("s3newbench_bytes_total", "Bytes transferred by completed S3 operations"),
+            &["operation", "bucket"],
+        )?;
+        // 1ms up to about 65s
+        let latency = HistogramVec::new(
+            HistogramOpts::new("s3newbench_operation_duration_seconds", "Response time of completed S3 operations")
+                .buckets(prometheus::exponential_buckets(0.001, 2.0, 17)?),
+            &["operation", "status", "bucket"],
+        )?;
+
+        let registry = Registry::new();
+        registry.register(Box::new(operations.clone()))?;
+        registry.register(Box::new(bytes.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        Ok(Self { registry, operations, bytes, latency })
+    }
+
+    pub fn observe(&self, operation: Operation, status: &str, bucket: &str, endpoint: &str, latency: Duration, bytes: usize) {
+        let op = operation.as_str();
+        self.operations.with_label_values(&[op, status, bucket, endpoint]).inc();
+        self.bytes.with_label_values(&[op, bucket]).inc_by(bytes as u64);
+        self.latency.with_label_values(&[op, status, bucket]).observe(latency.as_secs_f64());
+    }
+
+    pub fn observe_error(&self, operation: Operation, bucket: &str, endpoint: &str) {
+        self.operations.with_label_values(&[operation.as_str(), "error", bucket, endpoint]).inc();
+    }
+
+    // Binds right away so a busy port fails the run before it starts, then serves in the background
+    pub async fn serve(self: Arc<Self>, addr: &str) -> Result<(), BoxError> {
+        let listener = TcpListener::bind(addr).await
+            .map_err(|e| format!("Cannot listen on {} for metrics: {}", addr, e))?;
+        println!("Serving Prometheus metrics on http://{}/metrics", listener.local_addr()?);
+
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        eprintln!("Accepting a metrics connection failed: {}", e);
+                        continue;
+                    }
+                };
+                let metrics = Arc::clone(&self);
+                tokio::spawn(async move {
+                    if let Err(e) = metrics.respond(stream).await {
+                        eprintln!("Serving metrics failed: {}", e);
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+
+    // Minimal HTTP/1.1: one request per connection, only GET /metrics
+    async fn respond(&self, mut stream: TcpStream) -> Result<(), BoxError> {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+
+        let request_line = String::from_utf8_lossy(&request);
+        let mut parts = request_line.split_whitespace();
+        let (status, content_type, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some("/metrics")) => {
+                let encoder = TextEncoder::new();
+                let mut body = Vec::new();
+                encoder.encode(&self.registry.gather(), &mut body)?;
+                ("200 OK", encoder.format_type().to_string(), body)
+            }
+            _ => ("404 Not Found", "text/plain".to_string(), b"Not found\n".to_vec()),
+        };
+
+        let header = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            content_type,
+            body.len()
+        );
+        stream.write_all(header.as_bytes()).await?;
+        stream.write_all(&body).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::path::Path;
+
+use crate::BoxError;
+
+// Unit of compression and dedupe, matches the block size of most storage backends
+const BLOCK_SIZE: usize = 4096;
+// Distinct blocks objects draw their duplicates from in dedupe mode
+const DEDUPE_POOL_BLOCKS: usize = 1024;
+
+// Content of the objects written by a run. Every object gets fresh content, generated
+// before its timer starts so generation never shows up in the latencies.
+pub enum Payload {
+    // The same byte repeated, compresses and dedupes to almost nothing
+    Fill,
+    // Output of a cryptographically secure generator, defeats compression and dedupe
+    Random,
+    // Each block is random for 1/ratio of its length and zeros after that
+    Compressible { ratio: f64 },
+    // A block is unique with probability 1/ratio, otherwise a copy of one from a shared pool
+    Dedupe { ratio: f64, pool: Vec<u8> },
+    // Windows at random offsets into user-supplied data
+    Seed(Vec<u8>),
+}
+
+impl Payload {
+    // fill, random, compressible:RATIO, dedupe:RATIO or file:PATH (a file or a directory of files).
+    // The seed fixes the dedupe pool so keyed content can be regenerated by a later run.
+    pub fn parse(spec: &str, seed: u64) -> Result<Self, BoxError> {
+        let (kind, value) = match spec.split_once(':') {
+            Some((kind, value)) => (kind, Some(value)),
+            None => (spec, None),
+        };
+
+        match (kind.trim().to_lowercase().as_str(), value) {
+            ("fill", None) => Ok(Payload::Fill),
+            ("random", None) => Ok(Payload::Random),
+            ("compressible", Some(ratio)) => Ok(Payload::Compressible { ratio: Self::parse_ratio(ratio, spec)? }),
+            ("dedupe", Some(ratio)) => {
+                let mut pool = vec![0u8; DEDUPE_POOL_BLOCKS * BLOCK_SIZE];
+                StdRng::seed_from_u64(seed).fill(pool.as_mut_slice());
+                Ok(Payload::Dedupe { ratio: Self::parse_ratio(ratio, spec)?, pool })
+            }
+            ("file", Some(path)) => Ok(Payload::Seed(Self::load_seed(path)?)),
+            _ => Err(format!(
+                "Invalid payload '{}', expected fill, random, compressible:RATIO, dedupe:RATIO or file:PATH",
+                spec
+            ).into()),
+        }
+    }
+
+    fn parse_ratio(ratio: &str, spec: &str) -> Result<f64, BoxError> {
+        ratio.trim().parse::<f64>()
+            .ok()
+            .filter(|ratio| ratio.is_finite() && *ratio >= 1.0)
+            .ok_or_else(|| format!("Invalid ratio in payload '{}', expected a number of at least 1", spec).into())
+    }
+
+    fn load_seed(path: &str) -> Result<Vec<u8>, BoxError> {
+        let read = |path: &Path| std::fs::read(path).map_err(|e| format!("Cannot read seed file {}: {}", path.display(), e));
+
+        let path = Path::new(path);
+        let seed = if path.is_dir() {
+            let mut files: Vec<_> = std::fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<_, _>>()?;
+            files.retain(|file| file.is_file());
+            files.sort();
+
+            let mut seed = Vec::new();
+            for file in files {
+                seed.extend(read(&file)?);
+            }
+            seed
+        } else {
+            read(path)?
+        };
+
+        if seed.is_empty() {
+            return Err(format!("No seed data found in {}", path.display()).into());
+        }
+        Ok(seed)
+    }
+
+    pub fn generate<R: Rng>(&self, size: usize, rng: &mut R) -> Vec<u8> {
+        match self {
+            Payload::Fill => vec![b'a'; size],
+            Payload::Random => {
+                let mut data = vec![0u8; size];
+                rng.fill(data.as_mut_slice());
+                data
+            }
+            Payload::Compressible { ratio } => {
+                let mut data = vec![0u8; size];
+                let random_len = ((BLOCK_SIZE as f64 / ratio).ceil() as usize).min(BLOCK_SIZE);
+                for block in data.chunks_mut(BLOCK_SIZE) {
+                    let len = random_len.min(block.len());
+                    rng.fill(&mut block[..len]);
+                }
+                data
+            }
+            Payload::Dedupe { ratio, pool } => {
+                let mut data = vec![0u8; size];
+                let unique = 1.0 / ratio;
+                for block in data.chunks_mut(BLOCK_SIZE) {
+                    if rng.random::<f64>() < unique {
+                        rng.fill(block);
+                    } else {
+                        let start = rng.random_range(0..DEDUPE_POOL_BLOCKS) * BLOCK_SIZE;
+                        block.copy_from_slice(&pool[start..start + block.len()]);
+                    }
+                }
+                data
+            }
+            Payload::Seed(seed) => {
+                // The seed wraps around when the object is larger than it
+                let mut data = Vec::with_capacity(size);
+                let mut offset = rng.random_range(0..seed.len());
+                while data.len() < size {
+                    let len = (seed.len() - offset).min(size - data.len());
+                    data.extend_from_slice(&seed[offset..offset + len]);
+                    offset = 0;
+                }
+                data
+            }
+        }
+    }
+}
+
+// Generator for the content of one object, derived from its key and the seed only.
+// Writers and verifying readers must use the same seed, payload and build.
+pub fn keyed_rng(key: &str, seed: u64) -> StdRng {
+    // FNV-1a, unlike the std hasher it is stable across releases
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    StdRng::seed_from_u64(hash)
+}
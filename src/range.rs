@@ -0,0 +1,60 @@
+use rand::Rng;
+
+use crate::{BoxError, ObjectAnalyzer};
+
+// Byte-range access patterns used by columnar readers (Parquet, ORC)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RangePattern {
+    // The last N bytes, e.g. a Parquet footer
+    Tail(u64),
+    // `count` ranges of the given size at random offsets
+    Random { size: u64, count: usize },
+    // The whole object read in consecutive chunks
+    Sequential(u64),
+}
+
+impl RangePattern {
+    pub fn parse(spec: &str, ranges_per_object: usize) -> Result<Self, BoxError> {
+        let (kind, size) = spec.split_once(':')
+            .ok_or_else(|| format!("Invalid range pattern '{}', expected tail:SIZE, random:SIZE or sequential:SIZE", spec))?;
+
+        let size = ObjectAnalyzer::parse_size(size) as u64;
+        if size == 0 {
+            return Err(format!("Invalid range size in pattern '{}'", spec).into());
+        }
+
+        match kind.trim().to_lowercase().as_str() {
+            "tail" => Ok(RangePattern::Tail(size)),
+            "random" => Ok(RangePattern::Random { size, count: ranges_per_object.max(1) }),
+            "sequential" => Ok(RangePattern::Sequential(size)),
+            other => Err(format!("Unknown range pattern '{}', expected tail/random/sequential", other).into()),
+        }
+    }
+
+    // Inclusive (start, end) byte ranges to request from an object of the given size
+    pub fn plan<R: Rng>(&self, object_size: u64, rng: &mut R) -> Vec<(u64, u64)> {
+        if object_size == 0 {
+            return Vec::new();
+        }
+
+        match *self {
+            RangePattern::Tail(size) => {
+                vec![(object_size.saturating_sub(size), object_size - 1)]
+            }
+            RangePattern::Random { size, count } => (0..count)
+                .map(|_| {
+                    if size >= object_size {
+                        (0, object_size - 1)
+                    } else {
+                        let start = rng.random_range(0..=object_size - size);
+                        (start, start + size - 1)
+                    }
+                })
+                .collect(),
+            RangePattern::Sequential(chunk) => (0..object_size)
+                .step_by(chunk as usize)
+                .map(|start| (start, (start + chunk).min(object_size) - 1))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::BoxError;
+
+// Ordered benchmark phases read from a TOML or YAML file, e.g.
+//
+//   endpoint_url = "http://localhost:9000"
+//   bucket_name = "bench"
+//   object_size = "1MB"
+//
+//   [[phases]]
+//   name = "prepare"
+//   workload = "write"
+//   num_objects = 10000
+//
+//   [[phases]]
+//   name = "mixed"
+//   workload = "get=70,put=20,head=10"
+//   duration = "10m"
+//   rate = 500
+//   concurrency = 32
+//
+//   [[phases]]
+//   name = "cleanup"
+//   workload = "delete=100"
+//   num_objects = 10000
+//
+// Keys are the long command line flags with underscores or dashes. Top-level keys apply to
+// every phase, phases override them and flags given on the command line override both.
+#[derive(Deserialize)]
+pub struct Scenario {
+    pub phases: Vec<Phase>,
+    #[serde(flatten)]
+    defaults: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+pub struct Phase {
+    pub name: String,
+    #[serde(flatten)]
+    settings: Map<String, Value>,
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Self, BoxError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read scenario {}: {}", path, e))?;
+        let extension = Path::new(path).extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let scenario: Scenario = match extension.as_str() {
+            "toml" => toml::from_str(&content).map_err(|e| format!("Invalid scenario {}: {}", path, e))?,
+            "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| format!("Invalid scenario {}: {}", path, e))?,
+            _ => return Err(format!("Unknown scenario format '{}', expected .toml, .yaml or .yml", path).into()),
+        };
+        if scenario.phases.is_empty() {
+            return Err(format!("Scenario {} has no phases", path).into());
+        }
+        Ok(scenario)
+    }
+
+    // Command line for one phase: the file settings as flags, then the real command line
+    // so its flags take precedence
+    pub fn phase_args(&self, phase: &Phase, cli: &[String]) -> Result<Vec<String>, BoxError> {
+        // Merged before turning into flags, so a phase can also switch off a default boolean
+        let mut settings = Map::new();
+        for (key, value) in self.defaults.iter().chain(&phase.settings) {
+            settings.insert(key.replace('-', "_"), value.clone());
+        }
+
+        let mut args = vec![cli[0].clone(), "--phase".to_string(), phase.name.clone()];
+        for (key, value) in &settings {
+            push_flag(&mut args, key, value)
+                .map_err(|e| format!("Phase {}: {}", phase.name, e))?;
+        }
+        args.extend(strip_scenario_flag(&cli[1..]));
+        Ok(args)
+    }
+}
+
+fn push_flag(args: &mut Vec<String>, key: &str, value: &Value) -> Result<(), BoxError> {
+    let flag = format!("--{}", key.replace('_', "-"));
+    match value {
+        Value::Null | Value::Bool(false) => {}
+        Value::Bool(true) => args.push(flag),
+        Value::Number(number) => args.extend([flag, number.to_string()]),
+        Value::String(string) => args.extend([flag, string.clone()]),
+        // Repeatable flags such as sink
+        Value::Array(values) => {
+            for value in values {
+                push_flag(args, key, value)?;
+            }
+        }
+        Value::Object(_) => return Err(format!("Setting '{}' can't be a table", key).into()),
+    }
+    Ok(())
+}
+
+// Path given with --scenario, looked up before clap runs since the file may supply required flags
+pub fn path_from_args(cli: &[String]) -> Option<String> {
+    let mut args = cli.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--scenario" {
+            return args.next().cloned();
+        }
+        if let Some(path) = arg.strip_prefix("--scenario=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+fn strip_scenario_flag(cli: &[String]) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut iter = cli.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--scenario" {
+            iter.next();
+        } else if !arg.starts_with("--scenario=") {
+            args.push(arg.clone());
+        }
+    }
+    args
+}
@@ -0,0 +1,68 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::BoxError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrival {
+    Fixed,
+    Poisson,
+}
+
+impl Arrival {
+    pub fn parse(arrival: &str) -> Result<Self, BoxError> {
+        match arrival.trim().to_lowercase().as_str() {
+            "fixed" => Ok(Arrival::Fixed),
+            "poisson" => Ok(Arrival::Poisson),
+            other => Err(format!("Unknown arrival schedule '{}', expected fixed/poisson", other).into()),
+        }
+    }
+}
+
+struct ScheduleState {
+    next: Instant,
+    rng: StdRng,
+}
+
+// Open-loop schedule: start times are handed out at the target rate regardless of
+// how long operations take, so a stalled store shows up as queueing delay.
+pub struct Schedule {
+    arrival: Arrival,
+    interval_secs: f64,
+    state: Mutex<ScheduleState>,
+}
+
+impl Schedule {
+    pub fn new(rate: f64, arrival: Arrival) -> Result<Self, BoxError> {
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(format!("Rate must be a positive number of ops/s, got {}", rate).into());
+        }
+
+        Ok(Self {
+            arrival,
+            interval_secs: 1.0 / rate,
+            state: Mutex::new(ScheduleState {
+                next: Instant::now(),
+                rng: StdRng::from_os_rng(),
+            }),
+        })
+    }
+
+    pub fn next_start(&self) -> Instant {
+        let mut state = self.state.lock().unwrap();
+        let start = state.next;
+
+        let gap = match self.arrival {
+            Arrival::Fixed => self.interval_secs,
+            // Exponential inter-arrival times give a Poisson process
+            Arrival::Poisson => {
+                let u: f64 = state.rng.random();
+                -(1.0 - u).ln() * self.interval_secs
+            }
+        };
+        state.next += Duration::from_secs_f64(gap);
+        start
+    }
+}
@@ -0,0 +1,77 @@
+use elasticsearch::auth::Credentials;
+use elasticsearch::cert::{Certificate, CertificateValidation};
+use elasticsearch::http::Url;
+use elasticsearch::http::transport::{CloudConnectionPool, SingleNodeConnectionPool, Transport, TransportBuilder};
+use std::time::Duration;
+
+use crate::BoxError;
+
+// How to reach the results cluster
+pub struct ElasticConnection {
+    // Either a node URL or an Elastic Cloud ID
+    pub url: Option<String>,
+    pub cloud_id: Option<String>,
+    pub credentials: Option<Credentials>,
+    // PEM file with the CA that signed the cluster certificate
+    pub ca_cert: Option<String>,
+    // Accept any certificate, for test clusters only
+    pub insecure: bool,
+    // Bound on every request, so an unreachable cluster ends in a spill rather than a hang
+    pub timeout: Duration,
+}
+
+impl ElasticConnection {
+    pub fn transport(&self) -> Result<Transport, BoxError> {
+        let builder = match (&self.url, &self.cloud_id) {
+            (Some(url), None) => TransportBuilder::new(SingleNodeConnectionPool::new(Url::parse(url)?)),
+            (None, Some(cloud_id)) => TransportBuilder::new(CloudConnectionPool::new(cloud_id)?),
+            _ => return Err("Exactly one of an Elasticsearch URL or a Cloud ID is required".into()),
+        };
+
+        let mut builder = match &self.credentials {
+            Some(credentials) => builder.auth(credentials.clone()),
+            None => builder,
+        }
+        .timeout(self.timeout);
+        if self.insecure {
+            builder = builder.cert_validation(CertificateValidation::None);
+        } else if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read(path).map_err(|e| format!("Cannot read CA certificate {}: {}", path, e))?;
+            builder = builder.cert_validation(CertificateValidation::Full(Certificate::from_pem(&pem)?));
+        }
+        Ok(builder.build()?)
+    }
+}
+
+// Reads a secret from a file when one is given, else from the environment variable.
+// Trailing newlines left by editors and `echo` are dropped.
+pub fn read_secret(file: Option<&str>, env_var: &str) -> Result<Option<String>, BoxError> {
+    let secret = match file {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("Cannot read secret file {}: {}", path, e))?,
+        None => match std::env::var(env_var) {
+            Ok(value) => value,
+            Err(_) => return Ok(None),
+        },
+    };
+    Ok(Some(secret.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+// Basic auth when a username is given, else an API key, either `id:key` or the base64 encoded form
+pub fn credentials(
+    username: Option<&str>,
+    password: Option<String>,
+    api_key: Option<String>,
+) -> Result<Option<Credentials>, BoxError> {
+    match (username, password, api_key) {
+        (Some(_), _, Some(_)) => Err("Use either basic auth or an API key for Elasticsearch, not both".into()),
+        (Some(username), Some(password), None) => Ok(Some(Credentials::Basic(username.to_string(), password))),
+        (Some(username), None, None) => {
+            Err(format!("No password for Elasticsearch user {}, set ELASTIC_PASSWORD or --elastic-password-file", username).into())
+        }
+        (None, _, Some(api_key)) => match api_key.split_once(':') {
+            Some((id, key)) => Ok(Some(Credentials::ApiKey(id.to_string(), key.to_string()))),
+            None => Ok(Some(Credentials::EncodedApiKey(api_key))),
+        },
+        (None, _, None) => Ok(None),
+    }
+}
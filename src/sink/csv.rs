@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::Mutex;
+
+use super::MetricsSink;
+use crate::BoxError;
+
+struct CsvState {
+    writer: BufWriter<File>,
+    // Taken from the header of the file or else the first document, later documents are
+    // written in the same order
+    columns: Option<Vec<String>>,
+}
+
+// Appends documents as CSV rows, writing the header line into an empty file
+pub struct CsvSink {
+    state: Mutex<CsvState>,
+}
+
+impl CsvSink {
+    // Every phase of a scenario opens the file again, so rows are appended below the
+    // header already there
+    pub fn create(path: &str) -> Result<Self, BoxError> {
+        let file = File::options().create(true).read(true).append(true).open(path)
+            .map_err(|e| format!("Cannot open {}: {}", path, e))?;
+
+        let mut header = String::new();
+        BufReader::new(&file).read_line(&mut header)
+            .map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        let header = header.trim_end();
+        let columns = (!header.is_empty()).then(|| header.split(',').map(String::from).collect());
+
+        Ok(Self {
+            state: Mutex::new(CsvState {
+                writer: BufWriter::new(file),
+                columns,
+            }),
+        })
+    }
+
+    fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn format_value(value: Option<&serde_json::Value>) -> String {
+        match value {
+            None | Some(serde_json::Value::Null) => String::new(),
+            Some(serde_json::Value::String(s)) => Self::escape(s),
+            Some(other) => Self::escape(&other.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl MetricsSink for CsvSink {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    async fn write(&self, doc: &serde_json::Value) -> Result<(), BoxError> {
+        let fields = doc.as_object().ok_or("CSV sink only accepts JSON objects")?;
+
+        let mut state = self.state.lock().unwrap();
+        let CsvState { writer, columns } = &mut *state;
+        let columns = match columns {
+            Some(columns) => columns,
+            None => {
+                let header: Vec<String> = fields.keys().cloned().collect();
+                let line: Vec<String> = header.iter().map(|column| Self::escape(column)).collect();
+                writeln!(writer, "{}", line.join(","))?;
+                columns.insert(header)
+            }
+        };
+
+        let row: Vec<String> = columns.iter()
+            .map(|column| Self::format_value(fields.get(column)))
+            .collect();
+        writeln!(writer, "{}", row.join(","))?;
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), BoxError> {
+        self.state.lock().unwrap().writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use async_trait::async_trait;
+use elasticsearch::{BulkOperation, BulkParts, Elasticsearch};
+use elasticsearch::indices::IndicesPutIndexTemplateParts;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use super::MetricsSink;
+use super::connection::ElasticConnection;
+use super::index::IndexConfig;
+use crate::BoxError;
+
+// Documents waiting for the background task, beyond this the benchmark loop is held back
+const QUEUE_CAPACITY: usize = 100_000;
+// Once a batch has exhausted its retries, batches go straight to the spill file and the
+// cluster is only tried again this often, with a single attempt
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+pub struct BulkConfig {
+    // Flush once this many documents are buffered
+    pub batch_size: usize,
+    // Flush at least this often while documents are buffered
+    pub flush_interval: Duration,
+    // Attempts per batch after the first one
+    pub retries: u32,
+    // Documents that could not be indexed are appended here as NDJSON
+    pub spill_file: String,
+}
+
+// Hands documents to a background task that indexes them through the _bulk API,
+// so Elasticsearch latency stays out of the benchmark loop
+pub struct ElasticsearchSink {
+    sender: Mutex<Option<mpsc::Sender<serde_json::Value>>>,
+    indexer: Mutex<Option<JoinHandle<BulkIndexer>>>,
+}
+
+impl ElasticsearchSink {
+    // Connects to the cluster, installing the index template first when a name is given
+    pub async fn connect(
+        connection: &ElasticConnection,
+        config: BulkConfig,
+        index: IndexConfig,
+        template: Option<&str>,
+    ) -> Result<Self, BoxError> {
+        if config.flush_interval.is_zero() {
+            return Err("The bulk flush interval must be above zero".into());
+        }
+        let indexer = BulkIndexer::new(connection, config, index)?;
+        if let Some(template) = template {
+            indexer.install_template(template).await?;
+        }
+
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        let handle = tokio::spawn(indexer.run(receiver));
+        Ok(Self {
+            sender: Mutex::new(Some(sender)),
+            indexer: Mutex::new(Some(handle)),
+        })
+    }
+}
+
+#[async_trait]
+impl MetricsSink for ElasticsearchSink {
+    fn name(&self) -> &'static str {
+        "elasticsearch"
+    }
+
+    async fn write(&self, doc: &serde_json::Value) -> Result<(), BoxError> {
+        let sender = self.sender.lock().unwrap().clone()
+            .ok_or("Elasticsearch sink already flushed")?;
+        sender.send(doc.clone()).await
+            .map_err(|_| "Elasticsearch bulk indexer stopped")?;
+        Ok(())
+    }
+
+    // Closes the queue and waits until every buffered document has been sent
+    async fn flush(&self) -> Result<(), BoxError> {
+        self.sender.lock().unwrap().take();
+        let handle = self.indexer.lock().unwrap().take();
+        if let Some(handle) = handle {
+            let indexer = handle.await?;
+            if indexer.spilled > 0 {
+                println!(
+                    "{} documents could not be indexed and were spilled to {}, push them with `s3newbench replay`",
+                    indexer.spilled,
+                    indexer.config.spill_file
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+// Pushes a spill file written by an earlier run into the cluster.
+// Returns the number of documents indexed.
+pub async fn replay(connection: &ElasticConnection, path: &str, config: BulkConfig, index: IndexConfig) -> Result<usize, BoxError> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {}", path, e))?;
+    let mut indexer = BulkIndexer::new(connection, config, index)?;
+    let batch_size = indexer.config.batch_size.max(1);
+
+    let mut total = 0;
+    let mut batch = Vec::with_capacity(batch_size);
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        batch.push(indexer.index.prepare(serde_json::from_str(&line)?));
+        total += 1;
+        if batch.len() >= batch_size {
+            indexer.flush(std::mem::take(&mut batch)).await;
+        }
+    }
+    if !batch.is_empty() {
+        indexer.flush(batch).await;
+    }
+    indexer.close_spill()?;
+
+    if indexer.spilled > 0 {
+        return Err(format!(
+            "{} of {} documents could not be indexed, they were written to {}",
+            indexer.spilled, total, indexer.config.spill_file
+        ).into());
+    }
+    Ok(total)
+}
+
+struct BulkIndexer {
+    client: Elasticsearch,
+    config: BulkConfig,
+    index: IndexConfig,
+    spill: Option<BufWriter<File>>,
+    spilled: usize,
+    // Set while the cluster is considered down, to when it is probed next
+    next_probe: Option<Instant>,
+}
+
+impl BulkIndexer {
+    fn new(connection: &ElasticConnection, config: BulkConfig, index: IndexConfig) -> Result<Self, BoxError> {
+        Ok(Self {
+            client: Elasticsearch::new(connection.transport()?),
+            config,
+            index,
+            spill: None,
+            spilled: 0,
+            next_probe: None,
+        })
+    }
+
+    async fn install_template(&self, name: &str) -> Result<(), BoxError> {
+        let response = self.client.indices()
+            .put_index_template(IndicesPutIndexTemplateParts::Name(name))
+            .body(self.index.template_body())
+            .send()
+            .await?;
+        let status = response.status_code();
+        if !status.is_success() {
+            let reason = response.text().await.unwrap_or_default();
+            return Err(format!("Installing index template {} failed with {}: {}", name, status, reason).into());
+        }
+        Ok(())
+    }
+
+    async fn run(mut self, mut receiver: mpsc::Receiver<serde_json::Value>) -> Self {
+        let batch_size = self.config.batch_size.max(1);
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut ticker = tokio::time::interval(self.config.flush_interval);
+
+        loop {
+            tokio::select! {
+                doc = receiver.recv() => match doc {
+                    Some(doc) => {
+                        batch.push(self.index.prepare(doc));
+                        if batch.len() >= batch_size {
+                            self.flush(std::mem::take(&mut batch)).await;
+                        }
+                    }
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    if !batch.is_empty() {
+                        self.flush(std::mem::take(&mut batch)).await;
+                    }
+                }
+            }
+        }
+
+        if !batch.is_empty() {
+            self.flush(batch).await;
+        }
+        if let Err(e) = self.close_spill() {
+            eprintln!("Writing spill file {} failed: {}", self.config.spill_file, e);
+        }
+        self
+    }
+
+    async fn flush(&mut self, mut batch: Vec<serde_json::Value>) {
+        // A down cluster would hold every batch for all of its retries and back up the queue
+        let retries = match self.next_probe {
+            Some(next_probe) if Instant::now() < next_probe => {
+                self.spill_or_drop(&batch);
+                return;
+            }
+            Some(_) => 0,
+            None => self.config.retries,
+        };
+
+        let mut backoff = Duration::from_millis(200);
+        for attempt in 0..=retries {
+            if attempt > 0 {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+
+            match self.send(&batch).await {
+                Ok((retryable, rejected)) => {
+                    if !rejected.is_empty() {
+                        self.spill_or_drop(&rejected);
+                    }
+                    if retryable.is_empty() {
+                        if self.next_probe.take().is_some() {
+                            println!("Elasticsearch is reachable again, indexing resumed");
+                        }
+                        return;
+                    }
+                    // Only the documents rejected with a transient error are sent again
+                    batch = retryable;
+                }
+                Err(e) => eprintln!("Bulk indexing of {} documents failed: {}", batch.len(), e),
+            }
+        }
+
+        if self.next_probe.is_none() {
+            eprintln!(
+                "Elasticsearch is not keeping up, spilling documents to {} and trying again every {}s",
+                self.config.spill_file,
+                PROBE_INTERVAL.as_secs()
+            );
+        }
+        self.next_probe = Some(Instant::now() + PROBE_INTERVAL);
+        self.spill_or_drop(&batch);
+    }
+
+    // Returns the documents that should be retried and the ones rejected for good
+    async fn send(
+        &self,
+        batch: &[serde_json::Value],
+    ) -> Result<(Vec<serde_json::Value>, Vec<serde_json::Value>), BoxError> {
+        // Data streams only accept create, dated indices are picked per document
+        let body: Vec<BulkOperation<&serde_json::Value>> = batch.iter()
+            .map(|doc| {
+                let index = self.index.resolve(doc);
+                if self.index.data_stream {
+                    BulkOperation::create(doc).index(index).into()
+                } else {
+                    BulkOperation::index(doc).index(index).into()
+                }
+            })
+            .collect();
+
+        let response = self.client.bulk(BulkParts::None)
+            .body(body)
+            .send()
+            .await?;
+        let status = response.status_code();
+        if !status.is_success() {
+            return Err(format!("Elasticsearch responded with {}", status).into());
+        }
+
+        let response: serde_json::Value = response.json().await?;
+        if response["errors"].as_bool() != Some(true) {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        let mut retryable = Vec::new();
+        let mut rejected = Vec::new();
+        let items = response["items"].as_array().map(Vec::as_slice).unwrap_or_default();
+        for (doc, item) in batch.iter().zip(items) {
+            let result = if self.index.data_stream { &item["create"] } else { &item["index"] };
+            let status = result["status"].as_u64().unwrap_or(0);
+            if status == 429 || status >= 500 {
+                retryable.push(doc.clone());
+            } else if status >= 300 {
+                eprintln!("Elasticsearch rejected a document: {}", result["error"]);
+                rejected.push(doc.clone());
+            }
+        }
+        Ok((retryable, rejected))
+    }
+
+    fn spill_or_drop(&mut self, docs: &[serde_json::Value]) {
+        if let Err(e) = self.spill(docs) {
+            eprintln!("Dropping {} documents, spilling them failed: {}", docs.len(), e);
+        }
+    }
+
+    fn spill(&mut self, batch: &[serde_json::Value]) -> Result<(), BoxError> {
+        let writer = match &mut self.spill {
+            Some(writer) => writer,
+            None => {
+                let file = File::options().create(true).append(true).open(&self.config.spill_file)?;
+                self.spill.insert(BufWriter::new(file))
+            }
+        };
+
+        for doc in batch {
+            serde_json::to_writer(&mut *writer, doc)?;
+            writer.write_all(b"\n")?;
+        }
+        self.spilled += batch.len();
+        Ok(())
+    }
+
+    fn close_spill(&mut self) -> Result<(), BoxError> {
+        if let Some(mut writer) = self.spill.take() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
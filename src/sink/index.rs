@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use chrono::format::{Item, StrftimeItems};
+
+use crate::BoxError;
+
+// Where documents go: a plain index, a date-rolled index such as s3-perf-%Y.%m.%d,
+// or a data stream
+pub struct IndexConfig {
+    pub name: String,
+    pub data_stream: bool,
+}
+
+impl IndexConfig {
+    pub fn new(name: &str, data_stream: bool) -> Result<Self, BoxError> {
+        let config = Self { name: name.to_string(), data_stream };
+        if StrftimeItems::new(name).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("Invalid date pattern in index name '{}'", name).into());
+        }
+        // The template pattern is the part before the first date field, without one
+        // the template would apply to every index of the cluster
+        if config.is_dated() && config.pattern() == "*" {
+            return Err(format!("Index name '{}' must start with a fixed prefix before its date pattern", name).into());
+        }
+        if data_stream && config.is_dated() {
+            return Err("Data streams roll over by themselves, the name can't contain a date pattern".into());
+        }
+        Ok(config)
+    }
+
+    fn is_dated(&self) -> bool {
+        self.name.contains('%')
+    }
+
+    // Index name for a document, dated indices follow the document timestamp
+    pub fn resolve(&self, doc: &serde_json::Value) -> String {
+        if !self.is_dated() {
+            return self.name.clone();
+        }
+
+        let timestamp = doc["timestamp"].as_i64()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+        timestamp.format(&self.name).to_string()
+    }
+
+    // Data streams require an @timestamp field on every document
+    pub fn prepare(&self, mut doc: serde_json::Value) -> serde_json::Value {
+        if self.data_stream
+            && let Some(fields) = doc.as_object_mut()
+            && !fields.contains_key("@timestamp")
+            && let Some(timestamp) = fields.get("timestamp").cloned()
+        {
+            fields.insert("@timestamp".to_string(), timestamp);
+        }
+        doc
+    }
+
+    // Index pattern matching every index this config can write to
+    pub fn pattern(&self) -> String {
+        match self.name.split_once('%') {
+            Some((prefix, _)) => format!("{}*", prefix),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn template_body(&self) -> serde_json::Value {
+        let mut properties = serde_json::json!({
+            "timestamp": { "type": "date", "format": "epoch_millis" },
+            "latency": { "type": "float" },
+            "service_time": { "type": "float" },
+            "response_time": { "type": "float" },
+            "ttfb": { "type": "float" },
+            "transfer_time": { "type": "float" },
+            "throughput": { "type": "float" },
+            "rate": { "type": "float" },
+            "latency_exceeded": { "type": "boolean" },
+            "multipart": { "type": "boolean" },
+            "verified": { "type": "boolean" },
+            "size_in_bytes": { "type": "long" },
+            "range_start": { "type": "long" },
+            "range_end": { "type": "long" },
+            "part_number": { "type": "integer" },
+            "parts": { "type": "integer" },
+            "worker": { "type": "integer" },
+            "concurrency": { "type": "integer" },
+            "workload": { "type": "keyword" },
+            "phase": { "type": "keyword" },
+            "operation": { "type": "keyword" },
+            "size": { "type": "keyword" },
+            "payload": { "type": "keyword" },
+            "endpoint": { "type": "keyword" },
+            "retries": { "type": "integer" },
+            "checksum_algorithm": { "type": "keyword" },
+            "encryption": { "type": "keyword" },
+            "credentials": { "type": "keyword" },
+            "region": { "type": "keyword" },
+            "signing_name": { "type": "keyword" },
+            "addressing": { "type": "keyword" },
+            "tls": { "type": "keyword" },
+            "object_name": { "type": "keyword" },
+            "source": { "type": "keyword" },
+            "error": { "type": "keyword" },
+        });
+        if self.data_stream {
+            properties["@timestamp"] = serde_json::json!({ "type": "date", "format": "epoch_millis" });
+        }
+
+        let mut body = serde_json::json!({
+            "index_patterns": [self.pattern()],
+            // Above the built-in templates, which use 100
+            "priority": 200,
+            "template": {
+                "mappings": {
+                    "dynamic_templates": [{
+                        "strings_as_keywords": {
+                            "match_mapping_type": "string",
+                            "mapping": { "type": "keyword" }
+                        }
+                    }],
+                    "properties": properties
+                }
+            }
+        });
+        if self.data_stream {
+            body["data_stream"] = serde_json::json!({});
+        }
+        body
+    }
+}
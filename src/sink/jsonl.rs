@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Mutex;
+
+use super::MetricsSink;
+use crate::BoxError;
+
+// Appends one JSON document per line to a local file
+pub struct JsonLinesSink {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl JsonLinesSink {
+    pub fn create(path: &str) -> Result<Self, BoxError> {
+        let file = File::options().create(true).append(true).open(path)
+            .map_err(|e| format!("Cannot open {}: {}", path, e))?;
+        Ok(Self { writer: Mutex::new(BufWriter::new(file)) })
+    }
+}
+
+#[async_trait]
+impl MetricsSink for JsonLinesSink {
+    fn name(&self) -> &'static str {
+        "jsonl"
+    }
+
+    async fn write(&self, doc: &serde_json::Value) -> Result<(), BoxError> {
+        let mut writer = self.writer.lock().unwrap();
+        serde_json::to_writer(&mut *writer, doc)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), BoxError> {
+        self.writer.lock().unwrap().flush()?;
+        Ok(())
+    }
+}
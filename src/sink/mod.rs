@@ -0,0 +1,47 @@
+mod connection;
+mod csv;
+mod elastic;
+mod index;
+mod jsonl;
+mod otlp;
+mod stdout;
+
+use async_trait::async_trait;
+
+use crate::BoxError;
+
+pub use self::connection::{ElasticConnection, credentials, read_secret};
+pub use self::csv::CsvSink;
+pub use self::elastic::{BulkConfig, ElasticsearchSink, replay};
+pub use self::index::IndexConfig;
+pub use self::jsonl::JsonLinesSink;
+pub use self::otlp::OtlpSink;
+pub use self::stdout::StdoutSink;
+
+// Destination for the per-operation result documents
+#[async_trait]
+pub trait MetricsSink: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn write(&self, doc: &serde_json::Value) -> Result<(), BoxError>;
+
+    // Called once at the end of the run
+    async fn flush(&self) -> Result<(), BoxError> {
+        Ok(())
+    }
+}
+
+// Builds a sink from a --sink value: stdout, jsonl:PATH or csv:PATH
+pub fn from_spec(spec: &str) -> Result<Box<dyn MetricsSink>, BoxError> {
+    let (kind, path) = match spec.split_once(':') {
+        Some((kind, path)) => (kind, Some(path)),
+        None => (spec, None),
+    };
+
+    match (kind.trim().to_lowercase().as_str(), path) {
+        ("stdout", None) => Ok(Box::new(StdoutSink)),
+        ("jsonl", Some(path)) => Ok(Box::new(JsonLinesSink::create(path)?)),
+        ("csv", Some(path)) => Ok(Box::new(CsvSink::create(path)?)),
+        _ => Err(format!("Invalid sink '{}', expected stdout, jsonl:PATH or csv:PATH", spec).into()),
+    }
+}
@@ -0,0 +1,248 @@
+use async_trait::async_trait;
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Histogram, MeterProvider};
+use opentelemetry::trace::{Span, SpanKind, Status, Tracer, TracerProvider};
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::MetricsSink;
+use crate::BoxError;
+
+// How often latency histograms are pushed to the collector
+const METRICS_INTERVAL: Duration = Duration::from_secs(10);
+
+// Exports every result as an OTLP span and feeds per-operation latency histograms,
+// both over OTLP/HTTP to a collector
+pub struct OtlpSink {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+    tracer: SdkTracer,
+    latency: Histogram<f64>,
+    bucket: String,
+}
+
+impl OtlpSink {
+    // `endpoint` is the collector base URL, e.g. http://localhost:4318
+    pub fn new(endpoint: &str, bucket: &str) -> Result<Self, BoxError> {
+        let endpoint = endpoint.trim_end_matches('/');
+        let resource = Resource::builder().with_service_name("s3newbench").build();
+
+        let spans = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint))
+            .build()?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(spans)
+            .with_resource(resource.clone())
+            .build();
+
+        let metrics = MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/metrics", endpoint))
+            .build()?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(metrics).with_interval(METRICS_INTERVAL).build())
+            .with_resource(resource)
+            .build();
+
+        let tracer = tracer_provider.tracer("s3newbench");
+        let latency = meter_provider.meter("s3newbench")
+            .f64_histogram("s3.operation.duration")
+            .with_unit("ms")
+            .with_description("Response time of S3 operations")
+            .build();
+
+        Ok(Self {
+            tracer_provider,
+            meter_provider,
+            tracer,
+            latency,
+            bucket: bucket.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl MetricsSink for OtlpSink {
+    fn name(&self) -> &'static str {
+        "otlp"
+    }
+
+    async fn write(&self, doc: &serde_json::Value) -> Result<(), BoxError> {
+        let operation = doc["operation"].as_str().unwrap_or("unknown").to_string();
+        let error = doc["error"].as_str();
+        let latency_ms = doc["latency"].as_f64().unwrap_or(0.0);
+
+        // The document is created once the operation has completed
+        let end = doc["timestamp"].as_u64()
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+            .unwrap_or_else(SystemTime::now);
+        let start = end.checked_sub(Duration::from_secs_f64(latency_ms / 1000.0)).unwrap_or(end);
+
+        let mut attributes = vec![
+            KeyValue::new("s3.bucket", self.bucket.clone()),
+            KeyValue::new("s3.key", doc["object_name"].as_str().unwrap_or_default().to_string()),
+            KeyValue::new("s3.operation", operation.clone()),
+            KeyValue::new("size_in_bytes", doc["size_in_bytes"].as_i64().unwrap_or(0)),
+            KeyValue::new("status", error.unwrap_or("ok").to_string()),
+            KeyValue::new("workload", doc["workload"].as_str().unwrap_or_default().to_string()),
+            KeyValue::new("worker", doc["worker"].as_i64().unwrap_or(0)),
+            KeyValue::new("latency_exceeded", doc["latency_exceeded"].as_bool().unwrap_or(false)),
+            KeyValue::new("retries", doc["retries"].as_i64().unwrap_or(0)),
+        ];
+        if let Some(endpoint) = doc["endpoint"].as_str() {
+            attributes.push(KeyValue::new("server.address", endpoint.to_string()));
+        }
+        if let Some(part_number) = doc["part_number"].as_i64() {
+            attributes.push(KeyValue::new("s3.part_number", part_number));
+        }
+        if let (Some(range_start), Some(range_end)) = (doc["range_start"].as_i64(), doc["range_end"].as_i64()) {
+            attributes.push(KeyValue::new("s3.range", format!("bytes={}-{}", range_start, range_end)));
+        }
+
+        let mut span = self.tracer.span_builder(format!("S3 {}", operation))
+            .with_kind(SpanKind::Client)
+            .with_start_time(start)
+            .with_attributes(attributes)
+            .with_status(match error {
+                Some(error) => Status::error(error.to_string()),
+                None => Status::Ok,
+            })
+            .start(&self.tracer);
+        span.end_with_timestamp(end);
+
+        self.latency.record(latency_ms, &[
+            KeyValue::new("operation", operation),
+            KeyValue::new("bucket", self.bucket.clone()),
+        ]);
+        Ok(())
+    }
+
+    // The SDK exports from its own threads, shutting down blocks until they are drained
+    async fn flush(&self) -> Result<(), BoxError> {
+        let tracer_provider = self.tracer_provider.clone();
+        let meter_provider = self.meter_provider.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), BoxError> {
+            tracer_provider.shutdown()?;
+            meter_provider.shutdown()?;
+            Ok(())
+        })
+        .await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    type Received = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    // Stand-in collector keeping the path and body of every request, answering all with 200
+    async fn collector() -> (String, Received) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let received = Received::default();
+
+        let requests = Arc::clone(&received);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let requests = Arc::clone(&requests);
+                tokio::spawn(async move {
+                    // The exporters keep their connection alive between exports
+                    while let Some(request) = read_request(&mut stream).await {
+                        requests.lock().unwrap().push(request);
+                        let response = b"HTTP/1.1 200 OK\r\nContent-Type: application/x-protobuf\r\nContent-Length: 0\r\n\r\n";
+                        if stream.write_all(response).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        (url, received)
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> Option<(String, Vec<u8>)> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 8192];
+        let header_end = loop {
+            if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end + 4;
+            }
+            let read = stream.read(&mut buf).await.ok()?;
+            if read == 0 {
+                return None;
+            }
+            data.extend_from_slice(&buf[..read]);
+        };
+
+        let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+        let path = head.split_whitespace().nth(1)?.to_string();
+        let length = head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        let mut body = data.split_off(header_end);
+        while body.len() < length {
+            let read = stream.read(&mut buf).await.ok()?;
+            if read == 0 {
+                return None;
+            }
+            body.extend_from_slice(&buf[..read]);
+        }
+        Some((path, body))
+    }
+
+    fn contains(body: &[u8], text: &str) -> bool {
+        body.windows(text.len()).any(|w| w == text.as_bytes())
+    }
+
+    fn document(error: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "operation": "put",
+            "object_name": "object-1",
+            "latency": 12.5,
+            "timestamp": 1_700_000_000_000u64,
+            "size_in_bytes": 1024,
+            "workload": "write",
+            "worker": 0,
+            "latency_exceeded": false,
+            "retries": 2,
+            "error": error,
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn exports_spans_and_metrics_to_the_collector() {
+        let (url, received) = collector().await;
+        let sink = OtlpSink::new(&url, "bench").unwrap();
+        sink.write(&document(None)).await.unwrap();
+        sink.flush().await.unwrap();
+
+        let received = received.lock().unwrap();
+        let traces: Vec<_> = received.iter().filter(|(path, _)| path == "/v1/traces").collect();
+        assert!(!traces.is_empty(), "no span export received");
+        assert!(traces.iter().any(|(_, body)| contains(body, "S3 put") && contains(body, "retries")));
+        let metrics: Vec<_> = received.iter().filter(|(path, _)| path == "/v1/metrics").collect();
+        assert!(metrics.iter().any(|(_, body)| contains(body, "s3.operation.duration")));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failed_operations_carry_their_error() {
+        let (url, received) = collector().await;
+        let sink = OtlpSink::new(&url, "bench").unwrap();
+        sink.write(&document(Some("service error: SlowDown"))).await.unwrap();
+        sink.flush().await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert!(received.iter()
+            .any(|(path, body)| path == "/v1/traces" && contains(body, "service error: SlowDown")));
+    }
+}
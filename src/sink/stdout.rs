@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+
+use super::MetricsSink;
+use crate::BoxError;
+
+// Prints every document as one line of JSON
+pub struct StdoutSink;
+
+#[async_trait]
+impl MetricsSink for StdoutSink {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    async fn write(&self, doc: &serde_json::Value) -> Result<(), BoxError> {
+        println!("{}", doc);
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use rand::Rng;
+
+use crate::{BoxError, MAX_PUT_SIZE, ObjectAnalyzer};
+
+// Spread of object sizes written by a run
+#[derive(Debug, Clone, PartialEq)]
+pub enum SizeDistribution {
+    Fixed(usize),
+    // Any size between min and max, both included
+    Uniform { min: usize, max: usize },
+    // Sizes whose logarithm is normally distributed, capped at `max`
+    LogNormal { median: usize, sigma: f64, max: usize },
+    // Weighted sizes, e.g. 4KB:60,1MB:30,64MB:10
+    Histogram { sizes: Vec<(usize, u32)>, total: u32 },
+}
+
+impl SizeDistribution {
+    // 10MB, uniform:MIN-MAX, lognormal:MEDIAN,SIGMA[,MAX] or SIZE:WEIGHT,SIZE:WEIGHT,...
+    pub fn parse(spec: &str) -> Result<Self, BoxError> {
+        let spec = spec.trim();
+        let lower = spec.to_lowercase();
+
+        if let Some(range) = lower.strip_prefix("uniform:") {
+            let (min, max) = range.split_once('-')
+                .ok_or_else(|| format!("Invalid size range '{}', expected uniform:MIN-MAX", spec))?;
+            let (min, max) = (Self::parse_positive(min, spec)?, Self::parse_positive(max, spec)?);
+            if min > max {
+                return Err(format!("Invalid size range '{}', the minimum is above the maximum", spec).into());
+            }
+            return Ok(SizeDistribution::Uniform { min, max });
+        }
+
+        if let Some(params) = lower.strip_prefix("lognormal:") {
+            let params: Vec<&str> = params.split(',').collect();
+            if params.len() < 2 || params.len() > 3 {
+                return Err(format!("Invalid log-normal sizes '{}', expected lognormal:MEDIAN,SIGMA[,MAX]", spec).into());
+            }
+            let median = Self::parse_positive(params[0], spec)?;
+            let sigma: f64 = params[1].trim().parse()
+                .ok()
+                .filter(|sigma: &f64| sigma.is_finite() && *sigma >= 0.0)
+                .ok_or_else(|| format!("Invalid sigma '{}' in '{}'", params[1], spec))?;
+            // Without an explicit cap the tail is cut three sigmas above the median, and
+            // never above what a single PUT can write
+            let max = match params.get(2) {
+                Some(max) => Self::parse_positive(max, spec)?,
+                None => (median as f64 * (3.0 * sigma).exp()).min(MAX_PUT_SIZE as f64) as usize,
+            };
+            return Ok(SizeDistribution::LogNormal { median, sigma, max: max.max(median) });
+        }
+
+        if lower.contains(':') {
+            let mut sizes = Vec::new();
+            for entry in lower.split(',').filter(|entry| !entry.trim().is_empty()) {
+                let (size, weight) = entry.split_once(':')
+                    .ok_or_else(|| format!("Invalid size entry '{}', expected SIZE:WEIGHT", entry))?;
+                let weight: u32 = weight.trim().parse()
+                    .map_err(|_| format!("Invalid weight '{}' for size {}", weight, size))?;
+                sizes.push((Self::parse_positive(size, spec)?, weight));
+            }
+            let total = sizes.iter().map(|(_, weight)| weight).sum();
+            if total == 0 {
+                return Err(format!("Size histogram '{}' has no size with a positive weight", spec).into());
+            }
+            return Ok(SizeDistribution::Histogram { sizes, total });
+        }
+
+        Ok(SizeDistribution::Fixed(ObjectAnalyzer::parse_size(spec)))
+    }
+
+    fn parse_positive(size: &str, spec: &str) -> Result<usize, BoxError> {
+        match ObjectAnalyzer::parse_size(size) {
+            0 => Err(format!("Invalid size '{}' in '{}'", size.trim(), spec).into()),
+            size => Ok(size),
+        }
+    }
+
+    // Largest size the distribution can produce
+    pub fn max(&self) -> usize {
+        match self {
+            SizeDistribution::Fixed(size) => *size,
+            SizeDistribution::Uniform { max, .. } | SizeDistribution::LogNormal { max, .. } => *max,
+            SizeDistribution::Histogram { sizes, .. } => sizes.iter()
+                .filter(|(_, weight)| *weight > 0)
+                .map(|(size, _)| *size)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> usize {
+        match self {
+            SizeDistribution::Fixed(size) => *size,
+            SizeDistribution::Uniform { min, max } => rng.random_range(*min..=*max),
+            SizeDistribution::LogNormal { median, sigma, max } => {
+                // Box-Muller transform for a standard normal sample
+                let u1: f64 = 1.0 - rng.random::<f64>();
+                let u2: f64 = rng.random();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                ((*median as f64 * (sigma * z).exp()) as usize).clamp(1, *max)
+            }
+            SizeDistribution::Histogram { sizes, total } => {
+                let mut roll = rng.random_range(0..*total);
+                for (size, weight) in sizes {
+                    if roll < *weight {
+                        return *size;
+                    }
+                    roll -= weight;
+                }
+                unreachable!("roll is always below the total weight")
+            }
+        }
+    }
+}
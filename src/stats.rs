@@ -0,0 +1,164 @@
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::workload::Operation;
+
+// Latencies are recorded in microseconds, up to one hour
+const MAX_LATENCY_US: u64 = 3_600_000_000;
+
+struct OperationStats {
+    latency: Histogram<u64>,
+    errors: u64,
+    // Reads whose content differed from what was written
+    mismatches: u64,
+    bytes: u64,
+}
+
+impl Default for OperationStats {
+    fn default() -> Self {
+        Self {
+            latency: Histogram::new_with_bounds(1, MAX_LATENCY_US, 3).unwrap(),
+            errors: 0,
+            mismatches: 0,
+            bytes: 0,
+        }
+    }
+}
+
+// Per-operation latency histograms kept by each worker and merged at the end of the run
+#[derive(Default)]
+pub struct WorkerStats {
+    operations: HashMap<Operation, OperationStats>,
+}
+
+impl WorkerStats {
+    pub fn record(&mut self, operation: Operation, latency: Duration, bytes: usize) {
+        let stats = self.operations.entry(operation).or_default();
+        stats.latency.saturating_record((latency.as_micros() as u64).max(1));
+        stats.bytes += bytes as u64;
+    }
+
+    pub fn record_error(&mut self, operation: Operation) {
+        self.operations.entry(operation).or_default().errors += 1;
+    }
+
+    pub fn record_mismatch(&mut self, operation: Operation) {
+        self.operations.entry(operation).or_default().mismatches += 1;
+    }
+
+    pub fn merge(&mut self, other: WorkerStats) {
+        for (operation, other) in other.operations {
+            let stats = self.operations.entry(operation).or_default();
+            stats.latency.add(&other.latency).unwrap();
+            stats.errors += other.errors;
+            stats.mismatches += other.mismatches;
+            stats.bytes += other.bytes;
+        }
+    }
+
+    pub fn summarize(&self, elapsed: Duration) -> RunSummary {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let throughput = |bytes: u64| {
+            if elapsed_secs > 0.0 {
+                bytes as f64 / elapsed_secs / 1_000_000.0
+            } else {
+                0.0
+            }
+        };
+        let ms = |us: u64| us as f64 / 1000.0;
+
+        let mut operations: Vec<OperationSummary> = self.operations.iter()
+            .map(|(operation, stats)| {
+                let latency = &stats.latency;
+                let recorded = !latency.is_empty();
+                OperationSummary {
+                    operation: operation.as_str(),
+                    count: latency.len(),
+                    errors: stats.errors,
+                    mismatches: stats.mismatches,
+                    bytes: stats.bytes,
+                    min_ms: if recorded { ms(latency.min()) } else { 0.0 },
+                    mean_ms: if recorded { latency.mean() / 1000.0 } else { 0.0 },
+                    p50_ms: ms(latency.value_at_quantile(0.5)),
+                    p90_ms: ms(latency.value_at_quantile(0.9)),
+                    p99_ms: ms(latency.value_at_quantile(0.99)),
+                    p999_ms: ms(latency.value_at_quantile(0.999)),
+                    max_ms: ms(latency.max()),
+                    throughput_mb_s: throughput(stats.bytes),
+                }
+            })
+            .collect();
+        operations.sort_by_key(|summary| summary.operation);
+
+        // Parts are already accounted for by the whole-object put
+        let totals = operations.iter().filter(|summary| summary.operation != Operation::UploadPart.as_str());
+        let (ops, errors, mismatches, bytes) = totals.fold((0, 0, 0, 0), |(ops, errors, mismatches, bytes), summary| {
+            (ops + summary.count, errors + summary.errors, mismatches + summary.mismatches, bytes + summary.bytes)
+        });
+
+        RunSummary {
+            elapsed_secs,
+            ops,
+            errors,
+            mismatches,
+            bytes,
+            throughput_mb_s: throughput(bytes),
+            operations,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct OperationSummary {
+    pub operation: &'static str,
+    pub count: u64,
+    pub errors: u64,
+    pub mismatches: u64,
+    pub bytes: u64,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub p999_ms: f64,
+    pub max_ms: f64,
+    pub throughput_mb_s: f64,
+}
+
+#[derive(Serialize)]
+pub struct RunSummary {
+    pub elapsed_secs: f64,
+    pub ops: u64,
+    pub errors: u64,
+    pub mismatches: u64,
+    pub bytes: u64,
+    pub throughput_mb_s: f64,
+    pub operations: Vec<OperationSummary>,
+}
+
+impl RunSummary {
+    pub fn print(&self) {
+        println!(
+            "{:<12} {:>8} {:>7} {:>9} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "operation", "count", "errors", "mismatch", "min ms", "mean ms", "p50 ms", "p90 ms", "p99 ms", "p99.9 ms", "max ms", "MB/s"
+        );
+        for op in &self.operations {
+            println!(
+                "{:<12} {:>8} {:>7} {:>9} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+                op.operation, op.count, op.errors, op.mismatches, op.min_ms, op.mean_ms, op.p50_ms,
+                op.p90_ms, op.p99_ms, op.p999_ms, op.max_ms, op.throughput_mb_s
+            );
+        }
+        println!(
+            "Total: {} operations, {} errors, {} checksum mismatches in {:.2}s: {:.2} ops/s, {:.2} MB/s",
+            self.ops,
+            self.errors,
+            self.mismatches,
+            self.elapsed_secs,
+            if self.elapsed_secs > 0.0 { self.ops as f64 / self.elapsed_secs } else { 0.0 },
+            self.throughput_mb_s
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use rand::Rng;
+use std::sync::Mutex;
+
+use crate::BoxError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Get,
+    Put,
+    Delete,
+    Head,
+    // A single part of a multipart upload, never picked by a mix
+    UploadPart,
+    // A byte-range GET issued by a range pattern, never picked by a mix
+    GetRange,
+}
+
+impl Operation {
+    pub fn parse(operation: &str) -> Result<Self, BoxError> {
+        match operation.trim().to_lowercase().as_str() {
+            "get" => Ok(Operation::Get),
+            "put" => Ok(Operation::Put),
+            "delete" => Ok(Operation::Delete),
+            "head" => Ok(Operation::Head),
+            other => Err(format!("Unknown operation '{}', expected get/put/delete/head", other).into()),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Get => "get",
+            Operation::Put => "put",
+            Operation::Delete => "delete",
+            Operation::Head => "head",
+            Operation::UploadPart => "upload_part",
+            Operation::GetRange => "get_range",
+        }
+    }
+}
+
+// Weighted operation ratios, e.g. get=70,put=20,delete=5,head=5
+#[derive(Debug, Clone)]
+pub struct OperationMix {
+    weights: Vec<(Operation, u32)>,
+    total: u32,
+}
+
+impl OperationMix {
+    pub fn parse(spec: &str) -> Result<Self, BoxError> {
+        let mut weights = Vec::new();
+        for entry in spec.split(',').filter(|entry| !entry.trim().is_empty()) {
+            let (operation, weight) = entry.split_once('=')
+                .ok_or_else(|| format!("Invalid workload entry '{}', expected op=weight", entry))?;
+            let operation = Operation::parse(operation)?;
+            let weight: u32 = weight.trim().parse()
+                .map_err(|_| format!("Invalid weight '{}' for {}", weight, operation.as_str()))?;
+            if weights.iter().any(|(existing, _)| *existing == operation) {
+                return Err(format!("Operation {} listed twice in workload", operation.as_str()).into());
+            }
+            weights.push((operation, weight));
+        }
+
+        let total = weights.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return Err(format!("Workload '{}' has no operation with a positive weight", spec).into());
+        }
+        Ok(Self { weights, total })
+    }
+
+    pub fn pick<R: Rng>(&self, rng: &mut R) -> Operation {
+        let mut roll = rng.random_range(0..self.total);
+        for (operation, weight) in &self.weights {
+            if roll < *weight {
+                return *operation;
+            }
+            roll -= weight;
+        }
+        unreachable!("roll is always below the total weight")
+    }
+
+    pub fn includes(&self, operation: Operation) -> bool {
+        self.weights.iter().any(|(op, weight)| *op == operation && *weight > 0)
+    }
+}
+
+pub enum Workload {
+    Read,
+    Write,
+    Mixed(OperationMix),
+}
+
+impl Workload {
+    pub fn parse(workload: &str) -> Result<Self, BoxError> {
+        match workload.trim().to_lowercase().as_str() {
+            "read" => Ok(Workload::Read),
+            "write" => Ok(Workload::Write),
+            spec => Ok(Workload::Mixed(OperationMix::parse(spec)?)),
+        }
+    }
+
+    pub fn writes(&self) -> bool {
+        match self {
+            Workload::Read => false,
+            Workload::Write => true,
+            Workload::Mixed(mix) => mix.includes(Operation::Put),
+        }
+    }
+}
+
+// Keys currently known to exist, shared by the workers of a mixed run
+pub struct KeySet {
+    keys: Mutex<Vec<String>>,
+}
+
+impl KeySet {
+    pub fn new(keys: Vec<String>) -> Self {
+        Self { keys: Mutex::new(keys) }
+    }
+
+    pub fn insert(&self, key: String) {
+        self.keys.lock().unwrap().push(key);
+    }
+
+    pub fn random<R: Rng>(&self, rng: &mut R) -> Option<String> {
+        let keys = self.keys.lock().unwrap();
+        if keys.is_empty() {
+            return None;
+        }
+        Some(keys[rng.random_range(0..keys.len())].clone())
+    }
+
+    // Removes a random key so no other worker picks it while it is being deleted
+    pub fn take_random<R: Rng>(&self, rng: &mut R) -> Option<String> {
+        let mut keys = self.keys.lock().unwrap();
+        if keys.is_empty() {
+            return None;
+        }
+        let index = rng.random_range(0..keys.len());
+        Some(keys.swap_remove(index))
+    }
+}